@@ -4,10 +4,10 @@ use bevy_egui::{
     EguiContext,
 };
 use bevy_prototype_lyon::{
-    prelude::{GeometryBuilder, TessellationMode},
+    prelude::{Geometry, GeometryBuilder, TessellationMode},
     shapes::{self, RegularPolygonFeature},
 };
-use lyon_tessellation::FillOptions;
+use lyon_tessellation::{path::Path, FillOptions};
 
 use crate::{demo_camera_plugin::Page, MultishapeTag, WINDOW_WIDTH};
 
@@ -65,8 +65,9 @@ impl Plugin for DemoInspectorPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_resource(PolygonInspector::default())
             .add_resource(MultishapeInspector::default())
+            .add_startup_system(spawn_polygon.system())
             .add_system(update_polygon_inspector.system())
-            .add_system(replace_polygon.system())
+            .add_system(update_polygon.system())
             .add_system(update_multishape_inspector.system());
     }
 }
@@ -149,40 +150,63 @@ fn polygon_shape_properties(ui: &mut Ui, inspector: &mut ResMut<PolygonInspector
     }
 }
 
-fn replace_polygon(
+fn spawn_polygon(
     commands: &mut Commands,
-    query: Query<Entity, With<PolygonTag>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    inspector: Res<PolygonInspector>,
+) {
+    commands
+        .spawn(GeometryBuilder::build_as(
+            &polygon_shape(&inspector),
+            materials.add(ColorMaterial::color(polygon_color(&inspector))),
+            TessellationMode::Fill(FillOptions::default()),
+            polygon_transform(&inspector),
+        ))
+        .with(PolygonTag);
+}
+
+/// Mutating the `Path` is enough to make the plugin regenerate the mesh.
+fn update_polygon(
+    mut query: Query<(&mut Path, &mut Transform, &Handle<ColorMaterial>), With<PolygonTag>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     inspector: ChangedRes<PolygonInspector>,
 ) {
-    for entity in query.iter() {
-        commands.despawn(entity);
+    for (mut path, mut transform, material) in query.iter_mut() {
+        let mut builder = Path::builder();
+        polygon_shape(&inspector).add_geometry(&mut builder);
+        *path = builder.build();
+        *transform = polygon_transform(&inspector);
+
+        if let Some(material) = materials.get_mut(material) {
+            material.color = polygon_color(&inspector);
+        }
     }
-    let fill_mode = TessellationMode::Fill(FillOptions::default());
+}
 
-    let polygon = shapes::RegularPolygon {
+fn polygon_shape(inspector: &PolygonInspector) -> shapes::RegularPolygon {
+    shapes::RegularPolygon {
         sides: inspector.sides,
         feature: inspector.feature,
         center: Vec2::new(0.0, 0.0),
-    };
+        ..shapes::RegularPolygon::default()
+    }
+}
 
+fn polygon_color(inspector: &PolygonInspector) -> Color {
     let r = f32::from(inspector.color.r()) / 255.0;
     let g = f32::from(inspector.color.g()) / 255.0;
     let b = f32::from(inspector.color.b()) / 255.0;
     let a = f32::from(inspector.color.a()) / 255.0;
 
-    commands
-        .spawn(GeometryBuilder::build_as(
-            &polygon,
-            materials.add(ColorMaterial::color(Color::rgba_linear(r, g, b, a))),
-            fill_mode,
-            Transform {
-                translation: Vec3::new(X_OFFSET + inspector.pos_x, inspector.pos_y, 0.0),
-                rotation: Quat::from_axis_angle(Vec3::unit_z(), inspector.rotation),
-                ..Transform::default()
-            },
-        ))
-        .with(PolygonTag);
+    Color::rgba_linear(r, g, b, a)
+}
+
+fn polygon_transform(inspector: &PolygonInspector) -> Transform {
+    Transform {
+        translation: Vec3::new(X_OFFSET + inspector.pos_x, inspector.pos_y, 0.0),
+        rotation: Quat::from_axis_angle(Vec3::unit_z(), inspector.rotation),
+        ..Transform::default()
+    }
 }
 
 fn update_multishape_inspector(
@@ -13,6 +13,8 @@
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
 use bevy_prototype_lyon::prelude::*;
+#[cfg(feature = "collider")]
+use bevy_rapier2d::na::Point2;
 use bevy_rapier2d::{
     physics::{RapierConfiguration, RapierPhysicsPlugin},
     rapier::{
@@ -89,7 +91,7 @@ fn physics_showoff(commands: &mut Commands, materials: &mut ResMut<Assets<ColorM
         let rigid_body = RigidBodyBuilder::new_dynamic()
             .translation(x + WINDOW_WIDTH, y)
             .linvel(vel.x, vel.y);
-        let collider = ColliderBuilder::ball(CIRCLE_RADIUS)
+        let collider = ball_collider(&circle)
             .friction(0.0)
             .restitution(1.0)
             .collision_groups(circle_interaction_groups);
@@ -106,6 +108,38 @@ fn physics_showoff(commands: &mut Commands, materials: &mut ResMut<Assets<ColorM
     }
 }
 
+/// Returns a collider following the flattened outline of the circle, or a
+/// ball if its convex hull can't be generated.
+#[cfg(feature = "collider")]
+fn ball_collider(circle: &shapes::Circle) -> ColliderBuilder {
+    let hull = match ColliderData::new(circle, ColliderOptions::new(ColliderKind::ConvexHull)) {
+        Ok(ColliderData::ConvexHull(hull)) => hull,
+        Ok(other) => {
+            warn!("expected a convex hull, got {:?}", other);
+            return ColliderBuilder::ball(circle.radius);
+        }
+        Err(error) => {
+            warn!(
+                "failed to generate the convex hull of the circle: {:?}",
+                error
+            );
+            return ColliderBuilder::ball(circle.radius);
+        }
+    };
+    let hull = hull
+        .iter()
+        .map(|point| Point2::new(point.x, point.y))
+        .collect::<Vec<_>>();
+
+    ColliderBuilder::convex_hull(&hull).unwrap_or_else(|| ColliderBuilder::ball(circle.radius))
+}
+
+/// Returns a ball collider matching the circle.
+#[cfg(not(feature = "collider"))]
+fn ball_collider(circle: &shapes::Circle) -> ColliderBuilder {
+    ColliderBuilder::ball(circle.radius)
+}
+
 fn physics_static_geometry(commands: &mut Commands) {
     let wall_interaction_groups = InteractionGroups::new(0x0001, 0x0002);
 
@@ -152,8 +186,8 @@ fn path_builder_usage(commands: &mut Commands, materials: &mut ResMut<Assets<Col
 
     let fill_material = materials.add(ColorMaterial::color(Color::WHITE));
     let stroke_material = materials.add(ColorMaterial::color(Color::BLACK));
-    let fill_mode = TessellationMode::Fill(FillOptions::default());
-    let stroke_mode = TessellationMode::Stroke(
+    let mode = TessellationMode::FillAndStroke(
+        FillOptions::default(),
         StrokeOptions::default()
             .with_line_width(5.0)
             .with_line_join(LineJoin::Round),
@@ -164,20 +198,10 @@ fn path_builder_usage(commands: &mut Commands, materials: &mut ResMut<Assets<Col
         ..Transform::default()
     };
 
-    commands
-        .spawn(Camera2dBundle::default())
-        .spawn(GeometryBuilder::build_as(
-            &path,
-            fill_material,
-            fill_mode,
-            transform,
-        ))
-        .spawn(GeometryBuilder::build_as(
-            &path,
-            stroke_material,
-            stroke_mode,
-            transform,
-        ));
+    commands.spawn(Camera2dBundle::default()).spawn(
+        GeometryBuilder::build_as(&path, fill_material, mode, transform)
+            .with_outline_material(stroke_material),
+    );
 }
 
 fn show_multishape(commands: &mut Commands, materials: &mut ResMut<Assets<ColorMaterial>>) {
@@ -194,6 +218,7 @@ fn show_multishape(commands: &mut Commands, materials: &mut ResMut<Assets<ColorM
         sides: 3,
         center: Vec2::new(0.0, 158.0),
         feature: SIDE_LENGTH,
+        ..shapes::RegularPolygon::default()
     };
     let mut person = PathBuilder::new();
     // head
@@ -0,0 +1,305 @@
+//! Batched rendering of many shapes.
+//!
+//! Every shape is normally drawn with its own mesh, and thus its own draw
+//! call, which gets slow with thousands of shapes. The [`Batched`] shapes are
+//! instead merged, every frame, into a single mesh per material, that is drawn
+//! by a batch entity owned by the
+//! [`ShapePlugin`](crate::plugin::ShapePlugin).
+//!
+//! The batches are built in the [`BATCH`](crate::plugin::stage::BATCH) stage,
+//! once the [`GlobalTransform`]s of the frame have been computed.
+
+use crate::{
+    entity::Outline,
+    render::{ShapeGradient, SHAPE_PIPELINE_HANDLE},
+    tessellation::{append_mesh, build_mesh, VertexBuffers},
+};
+use bevy::{
+    asset::{Assets, Handle},
+    ecs::{Added, Changed, Commands, Entity, Local, Or, Query, ResMut, With, Without},
+    math::Vec2,
+    render::{
+        draw::Visible,
+        mesh::Mesh,
+        pipeline::{RenderPipeline, RenderPipelines},
+    },
+    sprite::{entity::SpriteBundle, ColorMaterial, Sprite, SpriteResizeMode},
+    transform::components::GlobalTransform,
+    utils::HashMap,
+};
+use std::cmp::Ordering;
+
+/// Marker component for the shapes drawn in a batch with the other shapes
+/// sharing their material, instead of with their own draw call.
+///
+/// The vertices of a batched shape are moved by its [`GlobalTransform`] and
+/// copied into the batch every frame, unless it is not [`Visible`]. The shapes
+/// of a batch are drawn from the lowest to the highest z coordinate, but a
+/// batch is sorted as a whole against the other transparent entities.
+///
+/// The outline of a
+/// [`FillAndStroke`](crate::utils::TessellationMode::FillAndStroke)
+/// shape is batched with the other shapes using its
+/// [`OutlineMaterial`](crate::entity::OutlineMaterial).
+///
+/// A shape with a gradient is still drawn on its own, since the batch mesh is
+/// drawn with a single [`ShapeGradient`].
+///
+/// Batching suits many small shapes, like particles. Since the batches are
+/// rebuilt every frame, it is not worth it for a few complex shapes. A batch
+/// is despawned, releasing its mesh and material, during the frames where no
+/// visible shape uses its material.
+///
+/// Removing the component draws the shape on its own again, with the shape
+/// pipeline.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_prototype_lyon::prelude::*;
+///
+/// fn spawn_particles(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+///     let material = materials.add(ColorMaterial::color(Color::ORANGE));
+///     let particle = shapes::Circle {
+///         radius: 2.0,
+///         ..shapes::Circle::default()
+///     };
+///     for i in 0..5000 {
+///         let position = Vec3::new((i % 100) as f32 * 8.0, (i / 100) as f32 * 8.0, 0.0);
+///         commands
+///             .spawn(GeometryBuilder::build_as(
+///                 &particle,
+///                 material.clone(),
+///                 TessellationMode::Fill(FillOptions::default()),
+///                 Transform::from_translation(position),
+///             ))
+///             .with(Batched);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Batched;
+
+/// The entity drawing the shapes of a material, with the mesh it owns.
+#[derive(Debug)]
+pub(crate) struct Batch {
+    entity: Entity,
+    mesh: Handle<Mesh>,
+}
+
+/// The batches of the materials used by visible [`Batched`] shapes.
+#[derive(Debug, Default)]
+pub(crate) struct Batches(HashMap<Handle<ColorMaterial>, Batch>);
+
+impl Batches {
+    /// Removes the batches of the materials that have no vertices in
+    /// `buffers`, and returns their entities, to be despawned.
+    ///
+    /// The handles of the removed batches are dropped, so that their mesh and
+    /// material can be freed.
+    fn remove_unused(
+        &mut self,
+        buffers: &HashMap<Handle<ColorMaterial>, VertexBuffers>,
+    ) -> Vec<Entity> {
+        let mut unused = Vec::new();
+        self.0.retain(|material, batch| {
+            let used = buffers.contains_key(material);
+            if !used {
+                unused.push(batch.entity);
+            }
+            used
+        });
+
+        unused
+    }
+
+    /// Replaces the meshes of the batches with the merged `buffers`, spawning
+    /// the missing batches and despawning the unused ones.
+    fn update(
+        &mut self,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        buffers: HashMap<Handle<ColorMaterial>, VertexBuffers>,
+    ) {
+        for entity in self.remove_unused(&buffers) {
+            commands.despawn(entity);
+        }
+        for (material, buffers) in buffers {
+            match self.0.get(&material) {
+                Some(batch) => {
+                    if let Some(existing) = meshes.get_mut(&batch.mesh) {
+                        *existing = build_mesh(&buffers);
+                    }
+                }
+                None => {
+                    let mesh = meshes.add(build_mesh(&buffers));
+                    let entity = spawn_batch(commands, mesh.clone(), material.clone());
+                    self.0.insert(material, Batch { entity, mesh });
+                }
+            }
+        }
+    }
+}
+
+/// A bevy system. Batches the outlines of the [`Batched`] shapes along with
+/// them, and stops batching them when their shape no longer is.
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_batched_outlines(
+    commands: &mut Commands,
+    batched: Query<&Outline, (With<Batched>, Or<(Changed<Outline>, Added<Batched>)>)>,
+    unbatched: Query<&Outline, Without<Batched>>,
+) {
+    for outline in batched.iter() {
+        commands.insert_one(outline.entity, Batched);
+    }
+    for entity in batched.removed::<Batched>() {
+        if let Ok(outline) = unbatched.get(*entity) {
+            commands.remove_one::<Batched>(outline.entity);
+        }
+    }
+}
+
+/// A bevy system. Restores the render pipeline of the shapes that are no
+/// longer [`Batched`].
+pub(crate) fn unbatch_shapes(mut query: Query<&mut RenderPipelines, Without<Batched>>) {
+    for entity in query.removed::<Batched>().to_vec() {
+        if let Ok(mut render_pipelines) = query.get_mut(entity) {
+            *render_pipelines = shape_pipelines();
+        }
+    }
+}
+
+/// Returns the render pipelines of a shape drawn on its own.
+fn shape_pipelines() -> RenderPipelines {
+    RenderPipelines::from_pipelines(vec![RenderPipeline::new(SHAPE_PIPELINE_HANDLE.typed())])
+}
+
+/// A bevy system. Merges the visible [`Batched`] shapes into the mesh of the
+/// batch of their material, spawning it if needed, and despawns the batches
+/// without any visible shape.
+///
+/// The render pipelines of the batched shapes are removed, so that they are
+/// only drawn by their batch, except for the shapes with a gradient.
+#[allow(clippy::type_complexity)]
+pub(crate) fn batch_shapes(
+    commands: &mut Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut batches: Local<Batches>,
+    mut shapes: Query<
+        (
+            &Handle<Mesh>,
+            &Handle<ColorMaterial>,
+            &GlobalTransform,
+            &Visible,
+            &ShapeGradient,
+            &mut RenderPipelines,
+        ),
+        With<Batched>,
+    >,
+) {
+    let mut visible_shapes = Vec::new();
+    for (mesh, material, transform, visible, gradient, mut render_pipelines) in shapes.iter_mut() {
+        if gradient.is_active() {
+            if render_pipelines.pipelines.is_empty() {
+                *render_pipelines = shape_pipelines();
+            }
+            continue;
+        }
+        if !render_pipelines.pipelines.is_empty() {
+            render_pipelines.pipelines.clear();
+        }
+        if visible.is_visible {
+            visible_shapes.push((mesh, material, transform));
+        }
+    }
+    let buffers = merge_meshes(&meshes, visible_shapes);
+    batches.update(commands, &mut meshes, buffers);
+}
+
+/// Merges the meshes of the visible shapes, from the lowest to the highest z
+/// coordinate, into vertex buffers per material.
+fn merge_meshes(
+    meshes: &Assets<Mesh>,
+    mut visible_shapes: Vec<(&Handle<Mesh>, &Handle<ColorMaterial>, &GlobalTransform)>,
+) -> HashMap<Handle<ColorMaterial>, VertexBuffers> {
+    visible_shapes.sort_by(|(.., a), (.., b)| {
+        a.translation
+            .z
+            .partial_cmp(&b.translation.z)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut buffers = HashMap::<Handle<ColorMaterial>, VertexBuffers>::default();
+    for (mesh, material, transform) in visible_shapes {
+        if let Some(mesh) = meshes.get(mesh) {
+            let buffers = buffers.entry(material.clone()).or_default();
+            append_mesh(buffers, mesh, transform);
+        }
+    }
+    buffers.retain(|_, buffers| !buffers.indices.is_empty());
+
+    buffers
+}
+
+/// Spawns the entity drawing the batch of a material.
+fn spawn_batch(
+    commands: &mut Commands,
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+) -> Entity {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                size: Vec2::new(1.0, 1.0),
+                resize_mode: SpriteResizeMode::Manual,
+            },
+            mesh,
+            material,
+            render_pipelines: shape_pipelines(),
+            ..SpriteBundle::default()
+        })
+        .with(ShapeGradient::default())
+        .current_entity()
+        .expect("the batch entity has just been spawned")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::HandleId;
+
+    fn material() -> Handle<ColorMaterial> {
+        Handle::weak(HandleId::random::<ColorMaterial>())
+    }
+
+    fn batch(id: u32) -> Batch {
+        Batch {
+            entity: Entity::new(id),
+            mesh: Handle::weak(HandleId::random::<Mesh>()),
+        }
+    }
+
+    #[test]
+    fn batches_without_members_are_removed() {
+        let (red, blue) = (material(), material());
+        let mut buffers = HashMap::<Handle<ColorMaterial>, VertexBuffers>::default();
+        buffers.insert(red.clone(), VertexBuffers::new());
+        buffers.insert(blue.clone(), VertexBuffers::new());
+
+        let mut batches = Batches::default();
+        batches.0.insert(red.clone(), batch(1));
+        batches.0.insert(blue.clone(), batch(2));
+        assert!(batches.remove_unused(&buffers).is_empty());
+        assert_eq!(batches.0.len(), 2);
+
+        buffers.remove(&blue);
+        assert_eq!(batches.remove_unused(&buffers), vec![Entity::new(2)]);
+        assert!(batches.0.contains_key(&red));
+        assert!(!batches.0.contains_key(&blue));
+
+        buffers.clear();
+        assert_eq!(batches.remove_unused(&buffers), vec![Entity::new(1)]);
+        assert!(batches.0.is_empty());
+    }
+}
@@ -0,0 +1,518 @@
+//! Boolean operations on geometries.
+//!
+//! The geometries are flattened into polygons, so the resulting path is only
+//! made of line segments. The operations compare every edge with every other
+//! edge, so their cost grows with the square of the number of edges: they are
+//! meant to be run once to build a shape, not every frame on complex paths.
+
+use crate::{
+    geometry::Geometry,
+    measure::{flatten, is_filled, winding_number, Edge},
+};
+use bevy::utils::{HashMap, HashSet};
+use lyon_tessellation::{geom, math::Point, path::Path, FillRule};
+use std::{cmp::Ordering, iter};
+
+/// The distance under which points are merged, relative to the size of the
+/// geometries.
+const SNAP_DISTANCE: f64 = 1e-6;
+/// The distance from an edge at which the area on each side of it is sampled,
+/// relative to the size of the geometries.
+const SIDE_DISTANCE: f64 = 1e-7;
+
+/// A boolean operation combining the areas of two geometries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanOp {
+    /// The area covered by any of the geometries.
+    Union,
+    /// The area covered by both geometries.
+    Intersection,
+    /// The area covered by the first geometry, but not by the second.
+    Difference,
+    /// The area covered by exactly one of the geometries.
+    Xor,
+}
+
+impl BooleanOp {
+    /// Returns `true` if a point inside or outside of each geometry is inside
+    /// of the result.
+    const fn contains(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            Self::Union => in_a || in_b,
+            Self::Intersection => in_a && in_b,
+            Self::Difference => in_a && !in_b,
+            Self::Xor => in_a != in_b,
+        }
+    }
+}
+
+/// Combines the areas of two geometries with a boolean operation.
+///
+/// The areas are determined with the given `fill_rule`, and the open
+/// sub-paths are closed, like when the geometries are filled. The curves are
+/// flattened with the given `tolerance`.
+///
+/// The resulting path is made of closed polygons that don't cross each other,
+/// so it can be filled with any fill rule. The outer boundaries are
+/// counter-clockwise and the holes are clockwise, in a y-up coordinate
+/// system. As a [`Path`] implements [`Geometry`], the result can be combined
+/// with other geometries or added to a
+/// [`GeometryBuilder`](crate::geometry::GeometryBuilder).
+///
+/// # Performance
+///
+/// Every pair of edges of the flattened geometries is tested once to find
+/// their intersections, so the cost is quadratic in the total number `n` of
+/// edges: `n * (n - 1) / 2` tests, about half a million for two circles
+/// flattened into 500 edges each. A larger `tolerance` produces fewer edges.
+/// Build the path once, rather than every frame, when the geometries are
+/// complex.
+///
+/// # Example
+///
+/// ```
+/// use bevy::math::Vec2;
+/// use bevy_prototype_lyon::{
+///     boolean::{boolean, BooleanOp},
+///     prelude::*,
+/// };
+///
+/// // Carve a door out of a wall.
+/// let wall = shapes::Rectangle {
+///     width: 200.0,
+///     height: 100.0,
+///     origin: shapes::RectangleOrigin::BottomLeft,
+/// };
+/// let door = shapes::Rectangle {
+///     width: 30.0,
+///     height: 60.0,
+///     origin: shapes::RectangleOrigin::CustomCenter(Vec2::new(100.0, 30.0)),
+/// };
+/// let path = boolean(&wall, &door, BooleanOp::Difference, FillRule::NonZero, 0.1);
+/// ```
+#[must_use]
+pub fn boolean(
+    a: &impl Geometry,
+    b: &impl Geometry,
+    op: BooleanOp,
+    fill_rule: FillRule,
+    tolerance: f32,
+) -> Path {
+    let edges_a = flatten(&a.to_path(), tolerance);
+    let edges_b = flatten(&b.to_path(), tolerance);
+    let scale = scale(edges_a.iter().chain(&edges_b));
+    let snap = scale * SNAP_DISTANCE;
+    let side = scale * SIDE_DISTANCE;
+
+    let edges = edges_a.iter().chain(&edges_b).copied().collect::<Vec<_>>();
+    let mut splits = vec![Vec::new(); edges.len()];
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            split_points(edges[i], edges[j], snap, &mut splits, (i, j));
+        }
+    }
+
+    let contains = |p: geom::Point<f64>| {
+        op.contains(
+            is_filled(winding_number(p, &edges_a), fill_rule),
+            is_filled(winding_number(p, &edges_b), fill_rule),
+        )
+    };
+
+    // The edges of the result, with its area on their left.
+    let mut boundary = Vec::new();
+    let mut seen = HashSet::default();
+    for (edge, points) in edges.iter().zip(&mut splits) {
+        for piece in split(*edge, points) {
+            let (from, to) = (key(piece.from), key(piece.to));
+            if !seen.insert((from.min(to), from.max(to))) {
+                continue;
+            }
+
+            let (from, to) = (piece.from.to_f64(), piece.to.to_f64());
+            let direction = to - from;
+            let normal = geom::vector(-direction.y, direction.x) * (side / direction.length());
+            let middle = from.lerp(to, 0.5);
+            match (contains(middle + normal), contains(middle - normal)) {
+                (true, false) => boundary.push(piece),
+                (false, true) => boundary.push(Edge {
+                    from: piece.to,
+                    to: piece.from,
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    build_polygons(&boundary)
+}
+
+/// Returns the largest dimension of the bounding box of the edges, or 1 if it
+/// is empty.
+fn scale<'a>(edges: impl Iterator<Item = &'a Edge>) -> f64 {
+    let mut points = edges.flat_map(|edge| iter::once(edge.from).chain(iter::once(edge.to)));
+    let first = match points.next() {
+        Some(first) => first,
+        None => return 1.0,
+    };
+    let (min, max) = points.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+    let size = f64::from((max.x - min.x).max(max.y - min.y));
+
+    if size > 0.0 {
+        size
+    } else {
+        1.0
+    }
+}
+
+/// Finds the points where the edges `a` and `b`, of indices `i` and `j`, meet,
+/// adding them to their `splits`.
+///
+/// Points closer than `snap` to an end of an edge are moved to it, and
+/// overlapping edges are split at each other's ends, so that their common
+/// parts become identical.
+fn split_points(a: Edge, b: Edge, snap: f64, splits: &mut [Vec<Point>], (i, j): (usize, usize)) {
+    if are_collinear(a, b, snap) {
+        splits[i].extend(inner_ends(a, b, snap));
+        splits[j].extend(inner_ends(b, a, snap));
+        return;
+    }
+
+    if let Some(point) = intersection(a, b, snap) {
+        if point != a.from && point != a.to {
+            splits[i].push(point);
+        }
+        if point != b.from && point != b.to {
+            splits[j].push(point);
+        }
+    }
+}
+
+/// Returns `true` if both ends of one of the edges are closer than `snap` to
+/// the line of the other one.
+fn are_collinear(a: Edge, b: Edge, snap: f64) -> bool {
+    let is_near = |point: Point, edge: Edge| {
+        let (from, direction) = (edge.from.to_f64(), edge.to.to_f64() - edge.from.to_f64());
+        (point.to_f64() - from).cross(direction).abs() / direction.length() <= snap
+    };
+
+    (is_near(b.from, a) && is_near(b.to, a)) || (is_near(a.from, b) && is_near(a.to, b))
+}
+
+/// Returns the ends of `other` that are inside of the collinear `edge`,
+/// farther than `snap` from its ends.
+fn inner_ends(edge: Edge, other: Edge, snap: f64) -> impl Iterator<Item = Point> {
+    let (from, direction) = (edge.from.to_f64(), edge.to.to_f64() - edge.from.to_f64());
+    let length = direction.length();
+    iter::once(other.from)
+        .chain(iter::once(other.to))
+        .filter(move |end| {
+            let t = (end.to_f64() - from).dot(direction) / (length * length);
+            t * length > snap && (1.0 - t) * length > snap
+        })
+}
+
+/// Returns the point where the edges `a` and `b` cross, if any, moved to an
+/// end of an edge if it is closer than `snap` to it.
+#[allow(clippy::cast_possible_truncation, clippy::many_single_char_names)]
+fn intersection(a: Edge, b: Edge, snap: f64) -> Option<Point> {
+    let (p, q) = (a.from.to_f64(), b.from.to_f64());
+    let (r, s) = (a.to.to_f64() - p, b.to.to_f64() - q);
+    let (r_length, s_length) = (r.length(), s.length());
+    let denominator = r.cross(s);
+    if denominator == 0.0 {
+        return None;
+    }
+    let t = (q - p).cross(s) / denominator;
+    let u = (q - p).cross(r) / denominator;
+    let on_edge = |t: f64, length: f64| t * length >= -snap && (1.0 - t) * length >= -snap;
+    if !on_edge(t, r_length) || !on_edge(u, s_length) {
+        return None;
+    }
+
+    Some(if t * r_length <= snap {
+        a.from
+    } else if (1.0 - t) * r_length <= snap {
+        a.to
+    } else if u * s_length <= snap {
+        b.from
+    } else if (1.0 - u) * s_length <= snap {
+        b.to
+    } else {
+        let point = p + r * t;
+        Point::new(point.x as f32, point.y as f32)
+    })
+}
+
+/// Returns the pieces of the edge between the given points.
+fn split(edge: Edge, points: &mut [Point]) -> Vec<Edge> {
+    let direction = edge.to - edge.from;
+    points.sort_by(|p, q| {
+        let (p, q) = (
+            (*p - edge.from).dot(direction),
+            (*q - edge.from).dot(direction),
+        );
+        p.partial_cmp(&q).unwrap_or(Ordering::Equal)
+    });
+
+    let mut pieces = Vec::with_capacity(points.len() + 1);
+    let mut from = edge.from;
+    for &to in points.iter().chain(iter::once(&edge.to)) {
+        if to != from {
+            pieces.push(Edge { from, to });
+            from = to;
+        }
+    }
+
+    pieces
+}
+
+/// Returns a key identifying the point, treating `-0.0` as `0.0`.
+fn key(point: Point) -> u64 {
+    (u64::from((point.x + 0.0).to_bits()) << 32) | u64::from((point.y + 0.0).to_bits())
+}
+
+/// Chains the edges into closed polygons.
+fn build_polygons(edges: &[Edge]) -> Path {
+    let mut outgoing = HashMap::<u64, Vec<usize>>::default();
+    for (i, edge) in edges.iter().enumerate() {
+        outgoing.entry(key(edge.from)).or_default().push(i);
+    }
+
+    let mut builder = Path::builder();
+    let mut used = vec![false; edges.len()];
+    for (first, edge) in edges.iter().enumerate() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        builder.begin(edge.from);
+
+        let mut current = edge.to;
+        while key(current) != key(edge.from) {
+            builder.line_to(current);
+            let next = outgoing.get_mut(&key(current)).and_then(|candidates| {
+                while let Some(next) = candidates.pop() {
+                    if !used[next] {
+                        return Some(next);
+                    }
+                }
+                None
+            });
+            match next {
+                Some(next) => {
+                    used[next] = true;
+                    current = edges[next].to;
+                }
+                None => break,
+            }
+        }
+        builder.end(true);
+    }
+
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lyon_tessellation::path::{traits::PathBuilder, PathEvent, Winding};
+
+    const TOLERANCE: f32 = 0.01;
+
+    /// Returns the path of a counter-clockwise rectangle.
+    fn rect(min: (f32, f32), max: (f32, f32)) -> Path {
+        let mut builder = Path::builder();
+        builder.begin(Point::new(min.0, min.1));
+        builder.line_to(Point::new(max.0, min.1));
+        builder.line_to(Point::new(max.0, max.1));
+        builder.line_to(Point::new(min.0, max.1));
+        builder.end(true);
+        builder.build()
+    }
+
+    /// Returns the signed area of the polygons of the path, positive for
+    /// counter-clockwise polygons.
+    fn area(path: &Path) -> f32 {
+        flatten(path, TOLERANCE)
+            .iter()
+            .map(|edge| edge.from.to_vector().cross(edge.to.to_vector()) / 2.0)
+            .sum()
+    }
+
+    fn contains(path: &Path, x: f64, y: f64) -> bool {
+        let edges = flatten(path, TOLERANCE);
+        is_filled(winding_number(geom::point(x, y), &edges), FillRule::NonZero)
+    }
+
+    /// Checks the area of the result of every operation on `a` and `b`, in
+    /// the order union, intersection, difference and xor.
+    fn assert_areas(a: &Path, b: &Path, areas: [f32; 4]) {
+        assert_areas_within(a, b, areas, 1e-3);
+    }
+
+    /// Checks the areas like [`assert_areas`], up to `max_error`.
+    fn assert_areas_within(a: &Path, b: &Path, areas: [f32; 4], max_error: f32) {
+        let ops = [
+            BooleanOp::Union,
+            BooleanOp::Intersection,
+            BooleanOp::Difference,
+            BooleanOp::Xor,
+        ];
+        for (op, expected) in ops.iter().zip(&areas) {
+            let result = boolean(a, b, *op, FillRule::NonZero, TOLERANCE);
+            let area = area(&result);
+            assert!(
+                (area - expected).abs() < max_error,
+                "{:?}: area {} instead of {}",
+                op,
+                area,
+                expected
+            );
+        }
+    }
+
+    /// Checks that the results of every operation contain the points inside
+    /// of `a` and `b` as expected.
+    fn assert_contains(a: &Path, b: &Path, points: &[(f64, f64)]) {
+        let ops = [
+            BooleanOp::Union,
+            BooleanOp::Intersection,
+            BooleanOp::Difference,
+            BooleanOp::Xor,
+        ];
+        for op in &ops {
+            let result = boolean(a, b, *op, FillRule::NonZero, TOLERANCE);
+            for &(x, y) in points {
+                let expected = op.contains(contains(a, x, y), contains(b, x, y));
+                assert_eq!(
+                    contains(&result, x, y),
+                    expected,
+                    "{:?} at ({}, {})",
+                    op,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn overlapping_rectangles() {
+        let a = rect((0.0, 0.0), (4.0, 4.0));
+        let b = rect((2.0, 2.0), (6.0, 6.0));
+        assert_areas(&a, &b, [28.0, 4.0, 12.0, 24.0]);
+        assert_contains(
+            &a,
+            &b,
+            &[(1.0, 1.0), (3.0, 3.0), (5.0, 5.0), (1.0, 5.0), (7.0, 7.0)],
+        );
+    }
+
+    #[test]
+    fn disjoint_rectangles() {
+        let a = rect((0.0, 0.0), (2.0, 2.0));
+        let b = rect((5.0, 0.0), (8.0, 2.0));
+        assert_areas(&a, &b, [10.0, 0.0, 4.0, 10.0]);
+        assert_contains(&a, &b, &[(1.0, 1.0), (6.0, 1.0), (3.0, 1.0)]);
+    }
+
+    #[test]
+    fn nested_rectangles() {
+        let outer = rect((0.0, 0.0), (10.0, 10.0));
+        let inner = rect((2.0, 2.0), (4.0, 4.0));
+        assert_areas(&outer, &inner, [100.0, 4.0, 96.0, 96.0]);
+        assert_areas(&inner, &outer, [100.0, 4.0, 0.0, 96.0]);
+        assert_contains(&outer, &inner, &[(1.0, 1.0), (3.0, 3.0), (11.0, 3.0)]);
+    }
+
+    #[test]
+    fn corner_touching_rectangles() {
+        let a = rect((0.0, 0.0), (2.0, 2.0));
+        let b = rect((2.0, 2.0), (4.0, 4.0));
+        assert_areas(&a, &b, [8.0, 0.0, 4.0, 8.0]);
+        assert_contains(&a, &b, &[(1.0, 1.0), (3.0, 3.0), (1.0, 3.0), (3.0, 1.0)]);
+    }
+
+    #[test]
+    fn collinear_shared_edges() {
+        // The right edge of `a` lies on the left edge of `b`.
+        let a = rect((0.0, 0.0), (2.0, 4.0));
+        let b = rect((2.0, 1.0), (5.0, 3.0));
+        assert_areas(&a, &b, [14.0, 0.0, 8.0, 14.0]);
+        assert_contains(&a, &b, &[(1.0, 2.0), (3.0, 2.0), (3.0, 3.5), (1.9, 0.5)]);
+
+        // The rectangles share their bottom edge.
+        let a = rect((0.0, 0.0), (4.0, 2.0));
+        let b = rect((1.0, 0.0), (3.0, 5.0));
+        assert_areas(&a, &b, [14.0, 4.0, 4.0, 10.0]);
+        assert_contains(&a, &b, &[(0.5, 1.0), (2.0, 1.0), (2.0, 4.0), (3.5, 3.0)]);
+
+        // Identical rectangles.
+        let a = rect((0.0, 0.0), (3.0, 3.0));
+        assert_areas(&a, &a.clone(), [9.0, 9.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn holes_are_clockwise() {
+        let outer = rect((0.0, 0.0), (10.0, 10.0));
+        let inner = rect((2.0, 2.0), (4.0, 4.0));
+        let result = boolean(
+            &outer,
+            &inner,
+            BooleanOp::Difference,
+            FillRule::NonZero,
+            TOLERANCE,
+        );
+        let mut areas = Vec::new();
+        for event in &result {
+            match event {
+                PathEvent::Begin { .. } => areas.push(0.0),
+                PathEvent::Line { from, to }
+                | PathEvent::End {
+                    last: from,
+                    first: to,
+                    ..
+                } => {
+                    if let Some(area) = areas.last_mut() {
+                        *area += from.to_vector().cross(to.to_vector()) / 2.0;
+                    }
+                }
+                _ => {}
+            }
+        }
+        areas.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        assert_eq!(areas, vec![-4.0, 100.0]);
+    }
+
+    #[test]
+    fn circle_and_rectangle() {
+        let mut builder = Path::builder();
+        builder.add_circle(Point::new(0.0, 0.0), 2.0, Winding::Positive);
+        let circle = builder.build();
+        let rectangle = rect((0.5, -3.0), (4.0, 3.0));
+
+        // The rectangle covers the circular segment right of x = 0.5.
+        let circle_area = 4.0 * std::f32::consts::PI;
+        let segment_area = 0.25_f32.acos().mul_add(4.0, -0.5 * 3.75_f32.sqrt());
+        let union = circle_area + 21.0 - segment_area;
+        // The flattening of the circle makes its area smaller by about
+        // `2 / 3 * TOLERANCE * perimeter`.
+        assert_areas_within(
+            &circle,
+            &rectangle,
+            [
+                union,
+                segment_area,
+                circle_area - segment_area,
+                union - segment_area,
+            ],
+            0.1,
+        );
+        assert_contains(
+            &circle,
+            &rectangle,
+            &[(0.0, 0.0), (1.0, 0.0), (3.0, 0.0), (0.0, 2.5), (-1.0, 1.0)],
+        );
+    }
+}
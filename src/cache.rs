@@ -0,0 +1,250 @@
+//! Deduplication of the meshes of identical shapes.
+//!
+//! When [`TessellationSettings::cache_meshes`] is enabled, shapes with the
+//! same [`Path`], [`TessellationMode`], [`UvMapping`], [`StrokeDash`] and
+//! [`StrokeTrim`] share the same mesh assets, that are tessellated only once.
+//!
+//! The cache doesn't keep the meshes alive: they are owned by the shapes using
+//! them, and the cache entry is evicted when the last of these shapes drops its
+//! handle.
+//!
+//! [`TessellationSettings::cache_meshes`]: crate::plugin::TessellationSettings::cache_meshes
+//! [`Path`]: lyon_tessellation::path::Path
+//! [`UvMapping`]: crate::paint::UvMapping
+//! [`StrokeDash`]: crate::stroke::StrokeDash
+//! [`StrokeTrim`]: crate::stroke::StrokeTrim
+
+use crate::{
+    paint::{FillUvMapping, StrokeUvMapping, UvMapping},
+    stroke::{StrokeDash, StrokeTrim},
+    tessellation::ShapeGeometry,
+    utils::TessellationMode,
+};
+use bevy::{
+    asset::{Assets, Handle, HandleId},
+    render::mesh::Mesh,
+    utils::{HashMap, HashSet},
+};
+use lyon_tessellation::{
+    math::Point,
+    path::{Event, Path},
+};
+
+/// Identifies the geometry of a shape, by encoding its path and its
+/// tessellation options.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct MeshKey(Vec<u32>);
+
+impl MeshKey {
+    pub(crate) fn new(geometry: &ShapeGeometry<'_>) -> Self {
+        let mut key = Vec::new();
+        push_path(&mut key, geometry.path);
+        // Tag the options so that they can't be confused with path events.
+        key.push(u32::MAX);
+        push_mode(&mut key, geometry.mode);
+        push_uv_mapping(&mut key, geometry.uv_mapping);
+        push_stroke_modifiers(&mut key, geometry.dash, *geometry.trim);
+
+        Self(key)
+    }
+}
+
+/// Encodes the events of the path, with the attributes of their endpoints.
+fn push_path(key: &mut Vec<u32>, path: &Path) {
+    // Endpoints carry their attributes, like the vertex colors.
+    let push_endpoint = |key: &mut Vec<u32>, (p, attributes): (Point, &[f32])| {
+        key.push(p.x.to_bits());
+        key.push(p.y.to_bits());
+        key.extend(attributes.iter().map(|a| a.to_bits()));
+    };
+    let push_point = |key: &mut Vec<u32>, p: Point| {
+        key.push(p.x.to_bits());
+        key.push(p.y.to_bits());
+    };
+
+    for event in path.iter_with_attributes() {
+        match event {
+            Event::Begin { at } => {
+                key.push(0);
+                push_endpoint(key, at);
+            }
+            Event::Line { to, .. } => {
+                key.push(1);
+                push_endpoint(key, to);
+            }
+            Event::Quadratic { ctrl, to, .. } => {
+                key.push(2);
+                push_point(key, ctrl);
+                push_endpoint(key, to);
+            }
+            Event::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => {
+                key.push(3);
+                push_point(key, ctrl1);
+                push_point(key, ctrl2);
+                push_endpoint(key, to);
+            }
+            Event::End { close, .. } => {
+                key.push(4);
+                key.push(u32::from(close));
+            }
+        }
+    }
+}
+
+/// Encodes the fill and stroke options of the tessellation mode.
+fn push_mode(key: &mut Vec<u32>, mode: &TessellationMode) {
+    let (fill, stroke) = match mode {
+        TessellationMode::Fill(fill) => (Some(fill), None),
+        TessellationMode::Stroke(stroke) => (None, Some(stroke)),
+        TessellationMode::FillAndStroke(fill, stroke) => (Some(fill), Some(stroke)),
+    };
+    if let Some(fill) = fill {
+        key.extend_from_slice(&[
+            0,
+            fill.tolerance.to_bits(),
+            fill.fill_rule as u32,
+            fill.sweep_orientation as u32,
+            u32::from(fill.handle_intersections),
+        ]);
+    }
+    if let Some(stroke) = stroke {
+        key.extend_from_slice(&[
+            1,
+            stroke.tolerance.to_bits(),
+            stroke.start_cap as u32,
+            stroke.end_cap as u32,
+            stroke.line_join as u32,
+            stroke.line_width.to_bits(),
+            stroke.miter_limit.to_bits(),
+        ]);
+    }
+}
+
+fn push_uv_mapping(key: &mut Vec<u32>, uv_mapping: &UvMapping) {
+    match uv_mapping.fill {
+        FillUvMapping::Stretch => key.push(0),
+        FillUvMapping::PreserveAspect => key.push(1),
+        FillUvMapping::Tile(size) => {
+            key.extend_from_slice(&[2, size.x.to_bits(), size.y.to_bits()])
+        }
+    }
+    match uv_mapping.stroke {
+        StrokeUvMapping::Stretch => key.push(0),
+        StrokeUvMapping::Tile(length) => key.extend_from_slice(&[1, length.to_bits()]),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn push_stroke_modifiers(key: &mut Vec<u32>, dash: &StrokeDash, trim: StrokeTrim) {
+    key.push(dash.pattern.len() as u32);
+    key.extend(dash.pattern.iter().map(|length| length.to_bits()));
+    key.push(dash.offset.to_bits());
+    key.push(trim.start.to_bits());
+    key.push(trim.end.to_bits());
+}
+
+/// The meshes drawing a shape and, for
+/// [`TessellationMode::FillAndStroke`], its outline.
+#[derive(Debug, Clone)]
+pub(crate) struct ShapeMeshes {
+    pub(crate) mesh: Handle<Mesh>,
+    pub(crate) outline_mesh: Option<Handle<Mesh>>,
+}
+
+/// A resource holding the meshes shared by identical shapes.
+///
+/// It is only filled when
+/// [`TessellationSettings::cache_meshes`](crate::plugin::TessellationSettings::cache_meshes)
+/// is enabled, and exposes statistics about its efficiency.
+#[derive(Debug, Default)]
+pub struct MeshCache {
+    /// Weak handles to the cached meshes.
+    entries: HashMap<MeshKey, ShapeMeshes>,
+    hits: u64,
+    misses: u64,
+}
+
+impl MeshCache {
+    /// Returns how many times the mesh of a shape has been found in the
+    /// cache.
+    #[must_use]
+    pub const fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns how many times the mesh of a shape had to be tessellated
+    /// because it was not in the cache.
+    #[must_use]
+    pub const fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns the number of distinct geometries in the cache.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the cache contains no geometry.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Looks up the meshes for `key`, updating the statistics.
+    pub(crate) fn get(&mut self, key: &MeshKey, meshes: &Assets<Mesh>) -> Option<ShapeMeshes> {
+        let found = self.lookup(key, meshes);
+        if found.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+
+        found
+    }
+
+    /// Looks up the meshes for `key`, returning strong handles to them.
+    ///
+    /// An entry whose meshes have been freed, but whose eviction has not been
+    /// processed yet, is ignored.
+    pub(crate) fn lookup(&self, key: &MeshKey, meshes: &Assets<Mesh>) -> Option<ShapeMeshes> {
+        let entry = self.entries.get(key)?;
+        let strong =
+            |handle: &Handle<Mesh>| meshes.get(handle).map(|_| meshes.get_handle(handle.id));
+
+        Some(ShapeMeshes {
+            mesh: strong(&entry.mesh)?,
+            outline_mesh: match entry.outline_mesh {
+                Some(ref outline_mesh) => Some(strong(outline_mesh)?),
+                None => None,
+            },
+        })
+    }
+
+    pub(crate) fn insert(&mut self, key: MeshKey, shape_meshes: &ShapeMeshes) {
+        self.entries.insert(
+            key,
+            ShapeMeshes {
+                mesh: Handle::weak(shape_meshes.mesh.id),
+                outline_mesh: shape_meshes
+                    .outline_mesh
+                    .as_ref()
+                    .map(|handle| Handle::weak(handle.id)),
+            },
+        );
+    }
+
+    /// Removes the entries using any of the given meshes, that have been
+    /// freed.
+    pub(crate) fn evict(&mut self, removed: &HashSet<HandleId>) {
+        self.entries.retain(|_, entry| {
+            !removed.contains(&entry.mesh.id)
+                && !entry
+                    .outline_mesh
+                    .as_ref()
+                    .map_or(false, |handle| removed.contains(&handle.id))
+        });
+    }
+}
@@ -0,0 +1,474 @@
+//! Collider data generated from the geometry of shapes.
+//!
+//! This module is only available with the `collider` feature. It doesn't
+//! depend on a physics engine: the generated [`ColliderData`] only contains
+//! vertices and indices, that can be passed to the collider constructors of
+//! engines like Rapier.
+//!
+//! A shape with a [`ShapeCollider`] component gets its collider data
+//! regenerated by the [`ShapePlugin`](crate::plugin::ShapePlugin) every time
+//! its [`Path`] or its [`ColliderOptions`] change, so the physics can follow
+//! the visuals.
+
+use crate::{geometry::Geometry, utils::Convert};
+use bevy::math::Vec2;
+use lyon_tessellation::{
+    geometry_builder::Positions,
+    math::Point,
+    path::{iterator::PathIterator, Path, PathEvent},
+    BuffersBuilder, FillOptions, FillRule, FillTessellator, TessellationError, VertexBuffers,
+};
+
+/// The cross product of consecutive edges under which a polygon is still
+/// considered convex, relative to their lengths.
+const CONVEXITY_TOLERANCE: f32 = 1e-6;
+
+/// The kind of collider generated from a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColliderKind {
+    /// The convex hull of the path.
+    ConvexHull,
+    /// Convex polygons covering the area of the path.
+    ConvexDecomposition,
+    /// The outline of the path, made of line segments.
+    Polyline,
+    /// Triangles covering the area of the path.
+    Trimesh,
+}
+
+/// How collider data is generated from a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColliderOptions {
+    /// The kind of collider.
+    pub kind: ColliderKind,
+    /// The maximum distance between the curves and their approximation by
+    /// line segments.
+    pub tolerance: f32,
+    /// The fill rule that determines the area of the path, for
+    /// [`ColliderKind::ConvexDecomposition`] and [`ColliderKind::Trimesh`].
+    pub fill_rule: FillRule,
+}
+
+impl Default for ColliderOptions {
+    fn default() -> Self {
+        Self {
+            kind: ColliderKind::ConvexHull,
+            tolerance: FillOptions::DEFAULT_TOLERANCE,
+            fill_rule: FillOptions::DEFAULT_FILL_RULE,
+        }
+    }
+}
+
+impl ColliderOptions {
+    /// Creates options generating the given kind of collider, with the default
+    /// tolerance and fill rule.
+    #[must_use]
+    pub fn new(kind: ColliderKind) -> Self {
+        Self {
+            kind,
+            ..Self::default()
+        }
+    }
+}
+
+/// Collider data generated from a path, in its coordinate system.
+///
+/// The polygons are counter-clockwise.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColliderData {
+    /// The vertices of the convex hull.
+    ConvexHull(Vec<Vec2>),
+    /// The vertices of convex polygons.
+    ConvexDecomposition(Vec<Vec<Vec2>>),
+    /// Line segments, as pairs of indices into the vertices.
+    Polyline {
+        /// The vertices of the segments.
+        vertices: Vec<Vec2>,
+        /// The indices of the ends of the segments.
+        indices: Vec<[u32; 2]>,
+    },
+    /// Triangles, as triples of indices into the vertices.
+    Trimesh {
+        /// The vertices of the triangles.
+        vertices: Vec<Vec2>,
+        /// The indices of the corners of the triangles.
+        indices: Vec<[u32; 3]>,
+    },
+}
+
+impl ColliderData {
+    /// Generates collider data from a geometry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the area of the geometry could not be tessellated,
+    /// for [`ColliderKind::ConvexDecomposition`] and [`ColliderKind::Trimesh`].
+    pub fn new(
+        geometry: &impl Geometry,
+        options: ColliderOptions,
+    ) -> Result<Self, TessellationError> {
+        let path = geometry.to_path();
+        let data = match options.kind {
+            ColliderKind::ConvexHull => Self::ConvexHull(convex_hull(&path, options.tolerance)),
+            ColliderKind::ConvexDecomposition => {
+                let (vertices, indices) = triangulate(&path, options)?;
+                Self::ConvexDecomposition(convex_decomposition(&vertices, &indices))
+            }
+            ColliderKind::Polyline => {
+                let (vertices, indices) = polyline(&path, options.tolerance);
+                Self::Polyline { vertices, indices }
+            }
+            ColliderKind::Trimesh => {
+                let (vertices, indices) = triangulate(&path, options)?;
+                Self::Trimesh { vertices, indices }
+            }
+        };
+
+        Ok(data)
+    }
+}
+
+/// Component that keeps the collider data of a shape in sync with its path.
+///
+/// The [`ShapePlugin`](crate::plugin::ShapePlugin) regenerates the data when
+/// the [`Path`] of the shape or the options change, in the
+/// [`SHAPE`](crate::plugin::stage::SHAPE) stage. Systems running after it can
+/// rebuild the colliders of the physics engine when the component has
+/// changed. If the data could not be generated, an error is logged and the
+/// previous data is kept.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_prototype_lyon::prelude::*;
+///
+/// fn spawn(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+///     commands
+///         .spawn(GeometryBuilder::build_as(
+///             &shapes::Star::default(),
+///             materials.add(ColorMaterial::color(Color::GOLD)),
+///             TessellationMode::Fill(FillOptions::default()),
+///             Transform::from_scale(Vec3::splat(50.0)),
+///         ))
+///         .with(ShapeCollider::new(ColliderOptions::new(
+///             ColliderKind::ConvexDecomposition,
+///         )));
+/// }
+///
+/// fn sync_physics(query: Query<&ShapeCollider, Changed<ShapeCollider>>) {
+///     for collider in query.iter() {
+///         if let ColliderData::ConvexDecomposition(polygons) = collider.data() {
+///             // Build the compound collider of the physics engine.
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeCollider {
+    /// How the collider data is generated.
+    pub options: ColliderOptions,
+    data: ColliderData,
+}
+
+impl Default for ShapeCollider {
+    fn default() -> Self {
+        Self::new(ColliderOptions::default())
+    }
+}
+
+impl ShapeCollider {
+    /// Creates a component generating collider data with the given options.
+    ///
+    /// The data is empty until the shape plugin generates it.
+    #[must_use]
+    pub const fn new(options: ColliderOptions) -> Self {
+        Self {
+            options,
+            data: match options.kind {
+                ColliderKind::ConvexHull => ColliderData::ConvexHull(Vec::new()),
+                ColliderKind::ConvexDecomposition => ColliderData::ConvexDecomposition(Vec::new()),
+                ColliderKind::Polyline => ColliderData::Polyline {
+                    vertices: Vec::new(),
+                    indices: Vec::new(),
+                },
+                ColliderKind::Trimesh => ColliderData::Trimesh {
+                    vertices: Vec::new(),
+                    indices: Vec::new(),
+                },
+            },
+        }
+    }
+
+    /// Returns the collider data generated from the path of the shape.
+    #[must_use]
+    pub const fn data(&self) -> &ColliderData {
+        &self.data
+    }
+
+    /// Regenerates the collider data from the given path.
+    pub(crate) fn update(&mut self, path: &Path) -> Result<(), TessellationError> {
+        self.data = ColliderData::new(path, self.options)?;
+        Ok(())
+    }
+}
+
+/// Returns the convex hull of the points of the flattened path, in
+/// counter-clockwise order.
+fn convex_hull(path: &Path, tolerance: f32) -> Vec<Vec2> {
+    let mut points = path
+        .iter()
+        .flattened(tolerance)
+        .filter_map(|event| match event {
+            PathEvent::Begin { at } => Some(at),
+            PathEvent::Line { to, .. } => Some(to),
+            _ => None,
+        })
+        .map(Convert::<Vec2>::convert)
+        .collect::<Vec<_>>();
+    points.sort_by(|a, b| {
+        (a.x, a.y)
+            .partial_cmp(&(b.x, b.y))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    // Andrew's monotone chain: the lower hull from left to right, then the
+    // upper hull from right to left.
+    let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() + 1);
+    let mut min_len = 2;
+    for (k, &point) in points.iter().chain(points.iter().rev().skip(1)).enumerate() {
+        if k == points.len() {
+            min_len = hull.len() + 1;
+        }
+        while hull.len() >= min_len
+            && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0
+        {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    // The last point is the first one.
+    hull.pop();
+
+    hull
+}
+
+/// Returns the cross product of the vectors going from `o` to `a` and to `b`,
+/// positive if they turn counter-clockwise.
+fn cross(o: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let (a, b) = (a - o, b - o);
+    a.x.mul_add(b.y, -a.y * b.x)
+}
+
+/// Returns the vertices and segments of the flattened path.
+#[allow(clippy::cast_possible_truncation)]
+fn polyline(path: &Path, tolerance: f32) -> (Vec<Vec2>, Vec<[u32; 2]>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut first = 0;
+    for event in path.iter().flattened(tolerance) {
+        let last = vertices.len() as u32;
+        match event {
+            PathEvent::Begin { at } => {
+                first = last;
+                vertices.push(at.convert());
+            }
+            PathEvent::Line { to, .. } => {
+                vertices.push(to.convert());
+                indices.push([last - 1, last]);
+            }
+            PathEvent::End {
+                last: last_point,
+                first: first_point,
+                close: true,
+            } if last_point != first_point => {
+                indices.push([last - 1, first]);
+            }
+            _ => {}
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Returns the triangles covering the area of the path.
+fn triangulate(
+    path: &Path,
+    options: ColliderOptions,
+) -> Result<(Vec<Vec2>, Vec<[u32; 3]>), TessellationError> {
+    let mut buffers: VertexBuffers<Point, u32> = VertexBuffers::new();
+    FillTessellator::new().tessellate_path(
+        path,
+        &FillOptions::tolerance(options.tolerance).with_fill_rule(options.fill_rule),
+        &mut BuffersBuilder::new(&mut buffers, Positions),
+    )?;
+
+    let vertices = buffers
+        .vertices
+        .iter()
+        .copied()
+        .map(Convert::<Vec2>::convert)
+        .collect::<Vec<_>>();
+    let indices = buffers
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+            // Make the triangles counter-clockwise.
+            if cross(
+                vertices[a as usize],
+                vertices[b as usize],
+                vertices[c as usize],
+            ) < 0.0
+            {
+                [a, c, b]
+            } else {
+                [a, b, c]
+            }
+        })
+        .collect();
+
+    Ok((vertices, indices))
+}
+
+/// Merges counter-clockwise triangles into convex polygons, by removing the
+/// diagonals between them whenever the result stays convex.
+fn convex_decomposition(vertices: &[Vec2], triangles: &[[u32; 3]]) -> Vec<Vec<Vec2>> {
+    let mut polygons = triangles
+        .iter()
+        .map(|triangle| triangle.to_vec())
+        .collect::<Vec<_>>();
+
+    // Grow every polygon as much as possible, before moving to the next one.
+    let mut i = 0;
+    while i < polygons.len() {
+        let mut j = i + 1;
+        while j < polygons.len() {
+            if let Some(polygon) = merge(vertices, &polygons[i], &polygons[j]) {
+                polygons[i] = polygon;
+                polygons.swap_remove(j);
+                j = i + 1;
+            } else {
+                j += 1;
+            }
+        }
+        i += 1;
+    }
+
+    polygons
+        .iter()
+        .map(|polygon| polygon.iter().map(|&i| vertices[i as usize]).collect())
+        .collect()
+}
+
+/// Returns the union of two counter-clockwise polygons sharing an edge, if it
+/// is convex.
+fn merge(vertices: &[Vec2], a: &[u32], b: &[u32]) -> Option<Vec<u32>> {
+    // A shared edge goes from `start` to `end` in `a`, and backwards in `b`.
+    let (edge_a, edge_b) = (0..a.len()).find_map(|edge_a| {
+        let (start, end) = (a[edge_a], a[(edge_a + 1) % a.len()]);
+        (0..b.len())
+            .find(|&edge_b| b[edge_b] == end && b[(edge_b + 1) % b.len()] == start)
+            .map(|edge_b| (edge_a, edge_b))
+    })?;
+
+    // Walk `a` from the end of the shared edge to its start, then `b` between
+    // the start and the end of the shared edge.
+    let polygon = (1..=a.len())
+        .map(|k| a[(edge_a + k) % a.len()])
+        .chain((2..b.len()).map(|k| b[(edge_b + k) % b.len()]))
+        .collect::<Vec<_>>();
+
+    let len = polygon.len();
+    let convex = (0..len).all(|k| {
+        let (previous, current, next) = (
+            vertices[polygon[k] as usize],
+            vertices[polygon[(k + 1) % len] as usize],
+            vertices[polygon[(k + 2) % len] as usize],
+        );
+        cross(previous, current, next)
+            >= -CONVEXITY_TOLERANCE * (current - previous).length() * (next - current).length()
+    });
+
+    if convex {
+        Some(polygon)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the path of a closed polygon.
+    fn polygon(points: &[(f32, f32)]) -> Path {
+        let mut builder = Path::builder();
+        builder.begin(Point::new(points[0].0, points[0].1));
+        for &(x, y) in &points[1..] {
+            builder.line_to(Point::new(x, y));
+        }
+        builder.end(true);
+        builder.build()
+    }
+
+    fn area(polygon: &[Vec2]) -> f32 {
+        let n = polygon.len();
+        (0..n)
+            .map(|i| cross(Vec2::zero(), polygon[i], polygon[(i + 1) % n]) / 2.0)
+            .sum()
+    }
+
+    #[test]
+    fn convex_hulls_skip_inner_points() {
+        // A square with a notch reaching its center, and a point in the
+        // middle of its bottom side.
+        let path = polygon(&[
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 2.0),
+            (1.0, 1.0),
+            (0.0, 2.0),
+        ]);
+
+        assert_eq!(
+            convex_hull(&path, 0.1),
+            vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(2.0, 2.0),
+                Vec2::new(0.0, 2.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn concave_polygons_are_decomposed() {
+        let path = polygon(&[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        let options = ColliderOptions::new(ColliderKind::ConvexDecomposition);
+        let parts = match ColliderData::new(&path, options) {
+            Ok(ColliderData::ConvexDecomposition(parts)) => parts,
+            other => panic!("unexpected collider data {:?}", other),
+        };
+
+        assert_eq!(parts.len(), 2);
+        for part in &parts {
+            let n = part.len();
+            assert!((0..n).all(|k| cross(part[k], part[(k + 1) % n], part[(k + 2) % n]) >= 0.0));
+        }
+        let total = parts.iter().map(|part| area(part)).sum::<f32>();
+        assert!((total - 3.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,302 @@
+//! Immediate-mode drawing of shapes, for debugging.
+//!
+//! The [`ShapeDebugDraw`] resource collects shapes to draw during a single
+//! frame, without spawning entities for them. The
+//! [`ShapePlugin`](crate::plugin::ShapePlugin) tessellates them together into
+//! the mesh of a single entity in the [`SHAPE`](crate::plugin::stage::SHAPE)
+//! stage, then clears the resource.
+
+use crate::{
+    geometry::{Geometry, GeometryBuilder},
+    paint::UvMapping,
+    render::{ShapeGradient, SHAPE_PIPELINE_HANDLE},
+    shapes,
+    stroke::{StrokeDash, StrokeTrim},
+    tessellation::{build_mesh, tessellate, ShapeGeometry, VertexBuffers},
+    utils::TessellationMode,
+};
+use bevy::{
+    asset::{Assets, Handle},
+    ecs::{Commands, Entity, Query, ResMut},
+    log::error,
+    math::{Vec2, Vec3},
+    render::{
+        color::Color,
+        draw::Visible,
+        mesh::Mesh,
+        pipeline::{RenderPipeline, RenderPipelines},
+    },
+    sprite::{entity::SpriteBundle, Sprite, SpriteResizeMode},
+    transform::components::Transform,
+};
+use lyon_tessellation::{path::Path, FillTessellator, StrokeTessellator};
+
+/// The factor applied to the RGB components of the color of a shape to get
+/// the color of its outline.
+const OUTLINE_SHADE: f32 = 0.5;
+
+/// The default z coordinate of the debug shapes, that draws them above the
+/// other shapes seen by a
+/// [`Camera2dBundle`](bevy::render::entity::Camera2dBundle).
+const DEFAULT_Z: f32 = 999.0;
+
+/// A resource collecting shapes to draw during the current frame only.
+///
+/// Every method adds the geometry of a shape, in world coordinates, with a
+/// color and a tessellation mode. Any [`Geometry`] can be drawn with
+/// [`path`](Self::path). The shapes are drawn above the other shapes, with a
+/// single mesh.
+///
+/// A [`TessellationMode::FillAndStroke`] shape draws its outline with a
+/// darker shade of its color, so that it stands out of the fill. To choose the
+/// color of the outline, draw the shape twice instead, with
+/// [`TessellationMode::Fill`] and [`TessellationMode::Stroke`].
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_prototype_lyon::prelude::*;
+///
+/// struct Waypoints(Vec<Vec2>);
+///
+/// fn draw_paths(mut debug_draw: ResMut<ShapeDebugDraw>, query: Query<&Waypoints>) {
+///     let stroke = TessellationMode::Stroke(StrokeOptions::default().with_line_width(2.0));
+///     for waypoints in query.iter() {
+///         debug_draw.polygon(&waypoints.0, false, Color::YELLOW, stroke);
+///         for point in &waypoints.0 {
+///             debug_draw.circle(*point, 4.0, Color::RED, stroke);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShapeDebugDraw {
+    /// The z coordinate of the debug shapes.
+    pub z: f32,
+    /// The paths of the shapes drawn during this frame, with their vertex
+    /// colors, and the tessellation mode of each path.
+    shapes: Vec<(Path, TessellationMode)>,
+    /// The entity drawing the debug shapes, with its mesh, once spawned.
+    entity: Option<(Entity, Handle<Mesh>)>,
+}
+
+impl Default for ShapeDebugDraw {
+    fn default() -> Self {
+        Self {
+            z: DEFAULT_Z,
+            shapes: Vec::new(),
+            entity: None,
+        }
+    }
+}
+
+impl ShapeDebugDraw {
+    /// Draws a line segment going from `from` to `to`.
+    ///
+    /// A line is only visible with [`TessellationMode::Stroke`].
+    pub fn line(
+        &mut self,
+        from: Vec2,
+        to: Vec2,
+        color: Color,
+        mode: TessellationMode,
+    ) -> &mut Self {
+        self.path(&shapes::Line(from, to), color, mode)
+    }
+
+    /// Draws a circle.
+    pub fn circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        color: Color,
+        mode: TessellationMode,
+    ) -> &mut Self {
+        self.path(&shapes::Circle { radius, center }, color, mode)
+    }
+
+    /// Draws an axis-aligned rectangle of the given size.
+    pub fn rect(
+        &mut self,
+        center: Vec2,
+        size: Vec2,
+        color: Color,
+        mode: TessellationMode,
+    ) -> &mut Self {
+        let rectangle = shapes::Rectangle {
+            width: size.x,
+            height: size.y,
+            origin: shapes::RectangleOrigin::CustomCenter(center),
+        };
+        self.path(&rectangle, color, mode)
+    }
+
+    /// Draws a polygon, or a polyline if it is not `closed`.
+    pub fn polygon(
+        &mut self,
+        points: &[Vec2],
+        closed: bool,
+        color: Color,
+        mode: TessellationMode,
+    ) -> &mut Self {
+        let polygon = shapes::Polygon {
+            points: points.to_vec(),
+            closed,
+        };
+        self.path(&polygon, color, mode)
+    }
+
+    /// Draws any geometry.
+    pub fn path(
+        &mut self,
+        geometry: &impl Geometry,
+        color: Color,
+        mode: TessellationMode,
+    ) -> &mut Self {
+        match mode {
+            TessellationMode::FillAndStroke(fill_options, stroke_options) => {
+                let outline_color = Color::rgba(
+                    color.r() * OUTLINE_SHADE,
+                    color.g() * OUTLINE_SHADE,
+                    color.b() * OUTLINE_SHADE,
+                    color.a(),
+                );
+                self.push(geometry, color, TessellationMode::Fill(fill_options));
+                self.push(
+                    geometry,
+                    outline_color,
+                    TessellationMode::Stroke(stroke_options),
+                );
+            }
+            _ => self.push(geometry, color, mode),
+        }
+
+        self
+    }
+
+    /// Adds the path of the geometry, with the given vertex color.
+    fn push(&mut self, geometry: &impl Geometry, color: Color, mode: TessellationMode) {
+        let mut builder = GeometryBuilder::new();
+        builder.add_with_color(geometry, color);
+        self.shapes.push((builder.build_path(), mode));
+    }
+
+    /// Returns `true` if no shape has been drawn during this frame.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+}
+
+/// A bevy system. Tessellates the shapes of the [`ShapeDebugDraw`] resource
+/// into the mesh of the debug entity, spawned the first time a shape is drawn,
+/// and clears the resource.
+pub(crate) fn draw_debug_shapes(
+    commands: &mut Commands,
+    mut debug_draw: ResMut<ShapeDebugDraw>,
+    mut fill_tess: ResMut<FillTessellator>,
+    mut stroke_tess: ResMut<StrokeTessellator>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&mut Visible, &mut Transform)>,
+) {
+    let debug_draw = &mut *debug_draw;
+    if debug_draw.entity.is_none() && debug_draw.is_empty() {
+        return;
+    }
+
+    let buffers = tessellate_shapes(
+        debug_draw.shapes.drain(..),
+        &mut fill_tess,
+        &mut stroke_tess,
+    );
+    let is_visible = !buffers.indices.is_empty();
+    let translation = Vec3::new(0.0, 0.0, debug_draw.z);
+    match debug_draw.entity {
+        Some((entity, ref mesh)) => {
+            if let Some(existing) = meshes.get_mut(mesh) {
+                *existing = build_mesh(&buffers);
+            }
+            if let Ok((mut visible, mut transform)) = query.get_mut(entity) {
+                visible.is_visible = is_visible;
+                transform.translation = translation;
+            }
+        }
+        None => {
+            let mesh = meshes.add(build_mesh(&buffers));
+            let entity = spawn_debug_entity(commands, mesh.clone(), is_visible, translation);
+            debug_draw.entity = Some((entity, mesh));
+        }
+    }
+}
+
+/// Tessellates the debug shapes into a single set of buffers, skipping the
+/// shapes whose tessellation fails.
+fn tessellate_shapes(
+    shapes: impl Iterator<Item = (Path, TessellationMode)>,
+    fill_tess: &mut FillTessellator,
+    stroke_tess: &mut StrokeTessellator,
+) -> VertexBuffers {
+    let uv_mapping = UvMapping::default();
+    let (dash, trim) = (StrokeDash::default(), StrokeTrim::default());
+    let mut buffers = VertexBuffers::new();
+    for (path, mode) in shapes {
+        let geometry = ShapeGeometry {
+            path: &path,
+            mode: &mode,
+            uv_mapping: &uv_mapping,
+            dash: &dash,
+            trim: &trim,
+        };
+        match tessellate(fill_tess, stroke_tess, &geometry) {
+            Ok(tessellation) => {
+                append(&mut buffers, &tessellation.buffers);
+                if let Some(outline_buffers) = tessellation.outline_buffers {
+                    append(&mut buffers, &outline_buffers);
+                }
+            }
+            Err(error) => error!("Tessellation of debug shape failed: {:?}", error),
+        }
+    }
+
+    buffers
+}
+
+/// Spawns the entity drawing the mesh of the debug shapes.
+fn spawn_debug_entity(
+    commands: &mut Commands,
+    mesh: Handle<Mesh>,
+    is_visible: bool,
+    translation: Vec3,
+) -> Entity {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                size: Vec2::new(1.0, 1.0),
+                resize_mode: SpriteResizeMode::Manual,
+            },
+            mesh,
+            render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
+                SHAPE_PIPELINE_HANDLE.typed(),
+            )]),
+            visible: Visible {
+                is_visible,
+                is_transparent: true,
+            },
+            transform: Transform::from_translation(translation),
+            ..SpriteBundle::default()
+        })
+        .with(ShapeGradient::default())
+        .current_entity()
+        .expect("the debug entity has just been spawned")
+}
+
+/// Appends the vertices and triangles of `source` to `target`.
+#[allow(clippy::cast_possible_truncation)]
+fn append(target: &mut VertexBuffers, source: &VertexBuffers) {
+    let offset = target.vertices.len() as u32;
+    target.vertices.extend_from_slice(&source.vertices);
+    target
+        .indices
+        .extend(source.indices.iter().map(|index| index + offset));
+}
@@ -1,9 +1,14 @@
 //! Custom Bevy [`Bundle`] for shapes.
 
-use crate::utils::TessellationMode;
+use crate::{
+    paint::{ShapePaint, UvMapping},
+    render::{ShapeGradient, SHAPE_PIPELINE_HANDLE},
+    stroke::{StrokeDash, StrokeTrim},
+    utils::TessellationMode,
+};
 use bevy::{
     asset::Handle,
-    ecs::Bundle,
+    ecs::{Bundle, Entity},
     math::Vec2,
     render::{
         draw::{Draw, Visible},
@@ -11,12 +16,46 @@ use bevy::{
         pipeline::{RenderPipeline, RenderPipelines},
         render_graph::base::MainPass,
     },
-    sprite::{ColorMaterial, Sprite, QUAD_HANDLE, SPRITE_PIPELINE_HANDLE},
+    sprite::{ColorMaterial, Sprite, SpriteResizeMode},
     transform::components::{GlobalTransform, Transform},
 };
 use lyon_tessellation::{path::Path, FillOptions};
 
-/// Component that marks a [`ShapeBundle`] as completed or not.
+/// Component holding the material used to draw the outline of a shape when its
+/// [`TessellationMode`] is [`FillAndStroke`](TessellationMode::FillAndStroke).
+///
+/// The outline is drawn by a child entity, so despawn the shape with
+/// `despawn_recursive` to remove it too. The child is shown and hidden along
+/// with the shape, following its [`Visible`] component.
+///
+/// It can be set with [`ShapeBundle::with_outline_material`].
+#[derive(Debug, Default, Clone)]
+pub struct OutlineMaterial(pub Handle<ColorMaterial>);
+
+/// Component that links a shape to the child entity that draws its outline.
+pub(crate) struct Outline {
+    pub(crate) entity: Entity,
+    pub(crate) mesh: Handle<Mesh>,
+}
+
+/// Marker component added to a shape whose geometry could not be tessellated.
+///
+/// It is removed once the shape is tessellated successfully. See
+/// [`ShapeTessellationError`](crate::plugin::ShapeTessellationError) for the
+/// details of the failure.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FailedTessellation;
+
+/// Component that marked a [`ShapeBundle`] as completed or not.
+///
+/// It is no longer part of the bundle nor updated by the plugin: the shapes
+/// are tessellated again whenever their [`Path`] or [`TessellationMode`]
+/// change.
+#[deprecated(
+    since = "0.3.0",
+    note = "shapes are re-tessellated on change; query `Changed<Path>` or `Handle<Mesh>` instead"
+)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct Processed(pub bool);
 
 /// A Bevy [`Bundle`] to represent a shape.
@@ -25,10 +64,15 @@ pub struct Processed(pub bool);
 pub struct ShapeBundle {
     pub path: Path,
     pub mode: TessellationMode,
-    pub processed: Processed,
+    pub paint: ShapePaint,
+    pub gradient: ShapeGradient,
+    pub uv_mapping: UvMapping,
+    pub dash: StrokeDash,
+    pub trim: StrokeTrim,
     pub sprite: Sprite,
     pub mesh: Handle<Mesh>,
     pub material: Handle<ColorMaterial>,
+    pub outline_material: OutlineMaterial,
     pub main_pass: MainPass,
     pub draw: Draw,
     pub visible: Visible,
@@ -42,10 +86,14 @@ impl Default for ShapeBundle {
         Self {
             path: Path::new(),
             mode: TessellationMode::Fill(FillOptions::default()),
-            processed: Processed(false),
-            mesh: QUAD_HANDLE.typed(),
+            paint: ShapePaint::default(),
+            gradient: ShapeGradient::default(),
+            uv_mapping: UvMapping::default(),
+            dash: StrokeDash::default(),
+            trim: StrokeTrim::default(),
+            mesh: Handle::default(),
             render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
-                SPRITE_PIPELINE_HANDLE.typed(),
+                SHAPE_PIPELINE_HANDLE.typed(),
             )]),
             visible: Visible {
                 is_visible: false,
@@ -55,11 +103,45 @@ impl Default for ShapeBundle {
             draw: Draw::default(),
             sprite: Sprite {
                 size: Vec2::new(1.0, 1.0),
-                ..Sprite::default()
+                resize_mode: SpriteResizeMode::Manual,
             },
             material: Handle::<ColorMaterial>::default(),
+            outline_material: OutlineMaterial::default(),
             transform: Transform::default(),
             global_transform: GlobalTransform::default(),
         }
     }
 }
+
+impl ShapeBundle {
+    /// Sets the material used to draw the outline of the shape, in
+    /// [`FillAndStroke`](TessellationMode::FillAndStroke) mode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_prototype_lyon::prelude::*;
+    ///
+    /// fn some_system(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    ///     let mode = TessellationMode::FillAndStroke(
+    ///         FillOptions::default(),
+    ///         StrokeOptions::default().with_line_width(4.0),
+    ///     );
+    ///     commands.spawn(
+    ///         GeometryBuilder::build_as(
+    ///             &shapes::Circle::default(),
+    ///             materials.add(ColorMaterial::color(Color::YELLOW)),
+    ///             mode,
+    ///             Transform::default(),
+    ///         )
+    ///         .with_outline_material(materials.add(ColorMaterial::color(Color::BLACK))),
+    ///     );
+    /// }
+    /// ```
+    #[must_use]
+    pub fn with_outline_material(mut self, material: Handle<ColorMaterial>) -> Self {
+        self.outline_material = OutlineMaterial(material);
+        self
+    }
+}
@@ -1,9 +1,24 @@
 //! Types for defining and using geometries.
 
-use bevy::{asset::Handle, sprite::ColorMaterial, transform::components::Transform};
-use lyon_tessellation::path::{path::Builder, Path};
+use bevy::{
+    asset::Handle,
+    math::Vec2,
+    render::color::Color,
+    sprite::ColorMaterial,
+    transform::components::{GlobalTransform, Transform},
+};
+use lyon_tessellation::{
+    geom::{CubicBezierSegment, QuadraticBezierSegment},
+    math::{Angle, Point, Transform as PathTransform},
+    path::{path::Builder, AttributeStore, Event, Path, PathEvent},
+};
 
-use crate::{entity::ShapeBundle, utils::TessellationMode};
+use crate::{
+    entity::ShapeBundle,
+    measure::{flatten, MeasuredPath},
+    path::{COLOR_ATTRIBUTES, NO_WIDTH, WIDTH_ATTRIBUTE},
+    utils::{Convert, TessellationMode},
+};
 
 /// Structs that implement this trait can be drawn as a shape. See the
 /// [`shapes`](crate::shapes) module for some examples.
@@ -48,23 +63,165 @@ use crate::{entity::ShapeBundle, utils::TessellationMode};
 pub trait Geometry {
     /// Adds the geometry of the shape to the given Lyon path [`Builder`].
     fn add_geometry(&self, b: &mut Builder);
+
+    /// Returns the path of the geometry.
+    ///
+    /// Unlike [`add_geometry`](Self::add_geometry), it can keep the custom
+    /// attributes of the path, like the stroke widths set with
+    /// [`PathBuilder::set_width`](crate::path::PathBuilder::set_width).
+    fn to_path(&self) -> Path {
+        let mut builder = Builder::new();
+        self.add_geometry(&mut builder);
+        builder.build()
+    }
 }
 
 /// This implementation permits to use a Lyon [`Path`] as a [`Geometry`].
 impl Geometry for Path {
     fn add_geometry(&self, b: &mut Builder) {
-        b.concatenate(&[self.as_slice()])
+        if self.num_attributes() == 0 {
+            b.concatenate(&[self.as_slice()]);
+            return;
+        }
+
+        for event in self {
+            match event {
+                PathEvent::Begin { at } => {
+                    b.begin(at);
+                }
+                PathEvent::Line { to, .. } => {
+                    b.line_to(to);
+                }
+                PathEvent::Quadratic { ctrl, to, .. } => {
+                    b.quadratic_bezier_to(ctrl, to);
+                }
+                PathEvent::Cubic {
+                    ctrl1, ctrl2, to, ..
+                } => {
+                    b.cubic_bezier_to(ctrl1, ctrl2, to);
+                }
+                PathEvent::End { close, .. } => b.end(close),
+            }
+        }
+    }
+
+    fn to_path(&self) -> Path {
+        self.clone()
+    }
+}
+
+/// A geometry with a 2D affine transform applied to its path.
+///
+/// The transforms are applied in the order of the method calls. Stroke widths
+/// set with [`PathBuilder::set_width`](crate::path::PathBuilder::set_width)
+/// are not scaled.
+///
+/// ```
+/// use bevy::math::Vec2;
+/// use bevy_prototype_lyon::prelude::*;
+///
+/// // An ellipse tilted by 30 degrees, centered on (100, 50).
+/// let ellipse = Transformed::new(shapes::Circle::default())
+///     .scaled(Vec2::new(40.0, 20.0))
+///     .rotated(30_f32.to_radians())
+///     .translated(Vec2::new(100.0, 50.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transformed<G> {
+    /// The transformed geometry.
+    pub geometry: G,
+    /// The transform applied to the path of the geometry.
+    pub transform: PathTransform,
+}
+
+impl<G> Transformed<G> {
+    /// Wraps a geometry, with the identity transform.
+    #[must_use]
+    pub fn new(geometry: G) -> Self {
+        Self::with_transform(geometry, PathTransform::identity())
+    }
+
+    /// Wraps a geometry, with the given transform.
+    #[must_use]
+    pub const fn with_transform(geometry: G, transform: PathTransform) -> Self {
+        Self {
+            geometry,
+            transform,
+        }
+    }
+
+    /// Translates the geometry by `translation`.
+    #[must_use]
+    pub fn translated(self, translation: Vec2) -> Self {
+        self.then(&PathTransform::translation(translation.x, translation.y))
+    }
+
+    /// Rotates the geometry counter-clockwise around the origin, by `angle`
+    /// radians.
+    #[must_use]
+    pub fn rotated(self, angle: f32) -> Self {
+        self.then(&PathTransform::rotation(Angle::radians(angle)))
+    }
+
+    /// Scales the geometry from the origin, by `scale.x` horizontally and
+    /// `scale.y` vertically.
+    #[must_use]
+    pub fn scaled(self, scale: Vec2) -> Self {
+        self.then(&PathTransform::scale(scale.x, scale.y))
+    }
+
+    /// Skews the geometry by `angles.x` radians along the x axis and
+    /// `angles.y` radians along the y axis, like the `skewX` and `skewY` SVG
+    /// transforms.
+    #[must_use]
+    pub fn skewed(self, angles: Vec2) -> Self {
+        self.then(&PathTransform::new(
+            1.0,
+            angles.y.tan(),
+            angles.x.tan(),
+            1.0,
+            0.0,
+            0.0,
+        ))
+    }
+
+    /// Applies `transform` after the current transform.
+    #[must_use]
+    pub fn then(mut self, transform: &PathTransform) -> Self {
+        self.transform = self.transform.then(transform);
+        self
+    }
+}
+
+impl<G: Geometry> Geometry for Transformed<G> {
+    fn add_geometry(&self, b: &mut Builder) {
+        self.to_path().add_geometry(b);
+    }
+
+    fn to_path(&self) -> Path {
+        self.geometry.to_path().transformed(&self.transform)
     }
 }
 
+/// The vertex color of the geometries added without a color.
+const WHITE: [f32; 4] = [1.0; 4];
+
 /// Allows the creation of shapes using geometries added to a path builder.
-pub struct GeometryBuilder(Builder);
+pub struct GeometryBuilder {
+    /// The added geometries, with their linear RGBA vertex color.
+    geometries: Vec<(Path, [f32; 4])>,
+    /// Whether any geometry has been added with a color.
+    colored: bool,
+}
 
 impl GeometryBuilder {
     /// Creates a new, empty `GeometryBuilder`.
     #[must_use]
-    pub fn new() -> Self {
-        Self(Builder::new())
+    pub const fn new() -> Self {
+        Self {
+            geometries: Vec::new(),
+            colored: false,
+        }
     }
 
     /// Adds a geometry to the path builder.
@@ -93,11 +250,146 @@ impl GeometryBuilder {
     /// }
     /// ```
     pub fn add(&mut self, shape: &impl Geometry) -> &mut Self {
-        shape.add_geometry(&mut self.0);
+        self.push(shape, WHITE)
+    }
+
+    /// Adds a geometry to the path builder, giving a color to its vertices.
+    ///
+    /// The vertex color is multiplied by the color of the material, so
+    /// differently colored geometries can be drawn with a single mesh and
+    /// material. The geometries added with [`add`](Self::add) are white.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_prototype_lyon::prelude::*;
+    ///
+    /// fn some_system(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    ///     let mut builder = GeometryBuilder::new();
+    ///     for (i, color) in [Color::RED, Color::GREEN, Color::BLUE].iter().enumerate() {
+    ///         let circle = shapes::Circle {
+    ///             radius: 10.0,
+    ///             center: Vec2::new(i as f32 * 30.0, 0.0),
+    ///         };
+    ///         builder.add_with_color(&circle, *color);
+    ///     }
+    ///
+    ///     commands.spawn(builder.build(
+    ///         materials.add(ColorMaterial::color(Color::WHITE)),
+    ///         TessellationMode::Fill(FillOptions::default()),
+    ///         Transform::default(),
+    ///     ));
+    /// }
+    /// ```
+    pub fn add_with_color(&mut self, shape: &impl Geometry, color: Color) -> &mut Self {
+        self.colored = true;
+        self.push(shape, color.into())
+    }
+
+    /// Adds a geometry to the path builder, applying a 2D affine transform to
+    /// it.
+    ///
+    /// This is a shorthand for adding a [`Transformed`] geometry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_prototype_lyon::prelude::*;
+    /// use lyon_tessellation::math::{Angle, Transform as PathTransform};
+    ///
+    /// fn some_system(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    ///     let blade = shapes::Rectangle {
+    ///         width: 10.0,
+    ///         height: 100.0,
+    ///         origin: shapes::RectangleOrigin::BottomLeft,
+    ///     };
+    ///     let mut builder = GeometryBuilder::new();
+    ///     for i in 0..3 {
+    ///         let angle = Angle::degrees(i as f32 * 120.0);
+    ///         builder.add_transformed(&blade, &PathTransform::rotation(angle));
+    ///     }
+    ///
+    ///     commands.spawn(builder.build(
+    ///         materials.add(ColorMaterial::color(Color::ORANGE_RED)),
+    ///         TessellationMode::Fill(FillOptions::default()),
+    ///         Transform::default(),
+    ///     ));
+    /// }
+    /// ```
+    pub fn add_transformed(
+        &mut self,
+        shape: &impl Geometry,
+        transform: &PathTransform,
+    ) -> &mut Self {
+        self.geometries
+            .push((shape.to_path().transformed(transform), WHITE));
+
+        self
+    }
+
+    fn push(&mut self, shape: &impl Geometry, color: [f32; 4]) -> &mut Self {
+        self.geometries.push((shape.to_path(), color));
 
         self
     }
 
+    /// Builds the path of all the geometries. The vertex colors are stored as
+    /// path attributes if any geometry has been added with a color or has
+    /// vertex colors, followed by the stroke widths if any geometry has some.
+    pub(crate) fn build_path(self) -> Path {
+        let max_attributes = self
+            .geometries
+            .iter()
+            .map(|(path, _)| path.num_attributes())
+            .max()
+            .unwrap_or(0);
+        if !self.colored && max_attributes == 0 {
+            let mut builder = Builder::new();
+            for (path, _) in &self.geometries {
+                builder.concatenate(&[path.as_slice()]);
+            }
+            return builder.build();
+        }
+
+        let num_attributes = if max_attributes > WIDTH_ATTRIBUTE {
+            WIDTH_ATTRIBUTE + 1
+        } else {
+            COLOR_ATTRIBUTES
+        };
+        let mut builder = Path::builder_with_attributes(num_attributes);
+        for (path, color) in &self.geometries {
+            let attributes = |source: &[f32]| endpoint_attributes(source, *color);
+            for event in path.iter_with_attributes() {
+                match event {
+                    Event::Begin { at: (at, a) } => {
+                        builder.begin(at, &attributes(a)[..num_attributes]);
+                    }
+                    Event::Line { to: (to, a), .. } => {
+                        builder.line_to(to, &attributes(a)[..num_attributes]);
+                    }
+                    Event::Quadratic {
+                        ctrl, to: (to, a), ..
+                    } => {
+                        builder.quadratic_bezier_to(ctrl, to, &attributes(a)[..num_attributes]);
+                    }
+                    Event::Cubic {
+                        ctrl1,
+                        ctrl2,
+                        to: (to, a),
+                        ..
+                    } => {
+                        builder.cubic_bezier_to(ctrl1, ctrl2, to, &attributes(a)[..num_attributes]);
+                    }
+                    Event::End { close, .. } => builder.end(close),
+                }
+            }
+        }
+
+        builder.build()
+    }
+
     /// Generates a [`ShapeBundle`] using the data contained in the path
     /// builder.
     #[must_use]
@@ -108,7 +400,7 @@ impl GeometryBuilder {
         transform: Transform,
     ) -> ShapeBundle {
         ShapeBundle {
-            path: self.0.build(),
+            path: self.build_path(),
             material,
             mode,
             transform,
@@ -152,3 +444,342 @@ impl Default for GeometryBuilder {
         Self::new()
     }
 }
+
+/// Returns the attributes of an endpoint of a geometry added with the given
+/// color, from its own attributes: its vertex color is multiplied by `color`,
+/// and its stroke width is kept.
+fn endpoint_attributes(source: &[f32], color: [f32; 4]) -> [f32; WIDTH_ATTRIBUTE + 1] {
+    let own_color = match *source {
+        [r, g, b, a, ..] => [r, g, b, a],
+        _ => WHITE,
+    };
+    let width = source.get(WIDTH_ATTRIBUTE).copied().unwrap_or(NO_WIDTH);
+
+    [
+        own_color[0] * color[0],
+        own_color[1] * color[1],
+        own_color[2] * color[2],
+        own_color[3] * color[3],
+        width,
+    ]
+}
+
+/// An axis-aligned bounding box, in the coordinate system of a path.
+///
+/// It is returned by [`bounds`], and it can also be used as a component: the
+/// [`ShapePlugin`](crate::plugin::ShapePlugin) keeps the `ShapeBounds` of a
+/// shape up to date with its [`Path`]. The bounds of an empty path are empty,
+/// at the origin.
+///
+/// The bounds only contain the path itself: the stroke of a shape extends
+/// beyond them by half of its line width.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_prototype_lyon::prelude::*;
+///
+/// // Insert `ShapeBounds` on a shape to have it updated by the plugin.
+/// fn spawn(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+///     commands
+///         .spawn(GeometryBuilder::build_as(
+///             &shapes::Circle::default(),
+///             materials.add(ColorMaterial::color(Color::AQUAMARINE)),
+///             TessellationMode::Fill(FillOptions::default()),
+///             Transform::default(),
+///         ))
+///         .with(ShapeBounds::default());
+/// }
+///
+/// // Log the size of the shapes on the screen.
+/// fn log_sizes(query: Query<(&ShapeBounds, &GlobalTransform), Changed<ShapeBounds>>) {
+///     for (bounds, transform) in query.iter() {
+///         info!("{}", bounds.transformed(transform).size());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ShapeBounds {
+    /// The corner with the smallest coordinates.
+    pub min: Vec2,
+    /// The corner with the largest coordinates.
+    pub max: Vec2,
+}
+
+impl ShapeBounds {
+    /// Returns the width and height of the bounds.
+    #[must_use]
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    /// Returns the center of the bounds.
+    #[must_use]
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+
+    /// Returns `true` if the point is inside of the bounds, or on their
+    /// boundary.
+    #[must_use]
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+
+    /// Returns `true` if the bounds overlap, or touch, `other`.
+    #[must_use]
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// Returns the bounds of the corners of these bounds, moved into world
+    /// coordinates by the given [`GlobalTransform`] and projected on the xy
+    /// plane.
+    #[must_use]
+    pub fn transformed(&self, transform: &GlobalTransform) -> Self {
+        let corners = [
+            self.min,
+            Vec2::new(self.max.x, self.min.y),
+            self.max,
+            Vec2::new(self.min.x, self.max.y),
+        ];
+        let mut corners = corners
+            .iter()
+            .map(|corner| transform.mul_vec3(corner.extend(0.0)).truncate());
+        let first = corners.next().unwrap_or_default();
+
+        corners.fold(Self::from_point(first), Self::union_point)
+    }
+
+    const fn from_point(point: Vec2) -> Self {
+        Self {
+            min: point,
+            max: point,
+        }
+    }
+
+    fn union_point(self, point: Vec2) -> Self {
+        Self {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+}
+
+/// Returns the axis-aligned bounding box of the geometry, or `None` if it is
+/// empty.
+///
+/// The bounds of the curves are exact, not computed from their control points.
+#[must_use]
+pub fn bounds(geometry: &impl Geometry) -> Option<ShapeBounds> {
+    path_bounds(&geometry.to_path())
+}
+
+/// Returns the axis-aligned bounding box of the path, or `None` if it is
+/// empty.
+pub(crate) fn path_bounds(path: &Path) -> Option<ShapeBounds> {
+    let mut bounds: Option<ShapeBounds> = None;
+    let mut add = |point: Point| {
+        let point = point.convert();
+        bounds = Some(bounds.map_or(ShapeBounds::from_point(point), |bounds| {
+            bounds.union_point(point)
+        }));
+    };
+
+    for event in path.iter() {
+        match event {
+            PathEvent::Begin { at } => add(at),
+            PathEvent::Line { to, .. } => add(to),
+            PathEvent::Quadratic { from, ctrl, to } => {
+                let curve = QuadraticBezierSegment { from, ctrl, to }.bounding_box();
+                add(curve.min);
+                add(curve.max);
+            }
+            PathEvent::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                let curve = CubicBezierSegment {
+                    from,
+                    ctrl1,
+                    ctrl2,
+                    to,
+                }
+                .bounding_box();
+                add(curve.min);
+                add(curve.max);
+            }
+            PathEvent::End { .. } => {}
+        }
+    }
+
+    bounds
+}
+
+/// Returns the area covered by the geometry.
+///
+/// The open sub-paths are closed, like when the geometry is filled, and the
+/// curves are flattened with the given `tolerance`. The signed areas of the
+/// sub-paths are added, so holes must go around in the opposite direction of
+/// their outer boundary, like the ones of [`shapes::Annulus`] or of the
+/// results of [`boolean`](crate::boolean::boolean).
+///
+/// [`shapes::Annulus`]: crate::shapes::Annulus
+#[must_use]
+pub fn area(geometry: &impl Geometry, tolerance: f32) -> f32 {
+    let (area, _) = area_and_moment(&geometry.to_path(), tolerance);
+
+    area.abs()
+}
+
+/// Returns the arc length of the geometry, the sum of the lengths of all its
+/// sub-paths.
+///
+/// The closed sub-paths include their closing segment. The curves are
+/// measured with the given `tolerance`.
+#[must_use]
+pub fn perimeter(geometry: &impl Geometry, tolerance: f32) -> f32 {
+    MeasuredPath::new(&geometry.to_path(), tolerance).length()
+}
+
+/// Returns the center of mass of the area covered by the geometry, computed
+/// like its [`area`].
+///
+/// If the area is zero, like for a line, the center of mass of the outline of
+/// the geometry is returned instead. `None` is returned if the geometry is
+/// empty.
+#[must_use]
+pub fn centroid(geometry: &impl Geometry, tolerance: f32) -> Option<Vec2> {
+    let path = geometry.to_path();
+    let (area, moment) = area_and_moment(&path, tolerance);
+    if area != 0.0 {
+        return Some(moment / area);
+    }
+
+    let (length, moment) =
+        flatten(&path, tolerance)
+            .iter()
+            .fold((0.0, Vec2::zero()), |(length, moment), edge| {
+                let edge_length = (edge.to - edge.from).length();
+                let middle: Vec2 = edge.from.lerp(edge.to, 0.5).convert();
+                (length + edge_length, moment + middle * edge_length)
+            });
+    if length > 0.0 {
+        Some(moment / length)
+    } else {
+        path_bounds(&path).map(|bounds| bounds.center())
+    }
+}
+
+/// Returns the signed area of the flattened path, and its first moment of
+/// area, the integral of the position over the area.
+#[allow(clippy::cast_possible_truncation)]
+fn area_and_moment(path: &Path, tolerance: f32) -> (f32, Vec2) {
+    let (mut area, mut moment_x, mut moment_y) = (0.0_f64, 0.0_f64, 0.0_f64);
+    for edge in flatten(path, tolerance) {
+        let (from, to) = (edge.from.to_f64(), edge.to.to_f64());
+        let cross = from.to_vector().cross(to.to_vector());
+        area += cross / 2.0;
+        moment_x += (from.x + to.x) * cross / 6.0;
+        moment_y += (from.y + to.y) * cross / 6.0;
+    }
+
+    (area as f32, Vec2::new(moment_x as f32, moment_y as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{Annulus, Circle, Rectangle, RectangleOrigin};
+    use std::f32::consts::PI;
+
+    const TOLERANCE: f32 = 0.01;
+
+    fn assert_near(value: f32, expected: f32, max_error: f32) {
+        assert!(
+            (value - expected).abs() <= max_error,
+            "{} instead of {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn rectangle_metrics() {
+        let rectangle = Rectangle {
+            width: 4.0,
+            height: 2.0,
+            origin: RectangleOrigin::BottomLeft,
+        };
+
+        let bounds = bounds(&rectangle).unwrap();
+        assert_eq!(bounds.min, Vec2::new(0.0, 0.0));
+        assert_eq!(bounds.max, Vec2::new(4.0, 2.0));
+        assert_near(area(&rectangle, TOLERANCE), 8.0, 1e-5);
+        assert_near(perimeter(&rectangle, TOLERANCE), 12.0, 1e-5);
+        let centroid = centroid(&rectangle, TOLERANCE).unwrap();
+        assert_near(centroid.x, 2.0, 1e-5);
+        assert_near(centroid.y, 1.0, 1e-5);
+    }
+
+    #[test]
+    fn circle_metrics() {
+        let circle = Circle {
+            radius: 10.0,
+            center: Vec2::new(3.0, -2.0),
+        };
+
+        // The exact bounds of the curves, while the area and perimeter are
+        // those of the flattened circle.
+        let bounds = bounds(&circle).unwrap();
+        assert_near(bounds.min.x, -7.0, 1e-3);
+        assert_near(bounds.max.y, 8.0, 1e-3);
+        assert_near(area(&circle, TOLERANCE), 100.0 * PI, 100.0 * PI * 1e-3);
+        assert_near(perimeter(&circle, TOLERANCE), 20.0 * PI, 20.0 * PI * 1e-3);
+        let centroid = centroid(&circle, TOLERANCE).unwrap();
+        assert_near(centroid.x, 3.0, 1e-3);
+        assert_near(centroid.y, -2.0, 1e-3);
+    }
+
+    #[test]
+    fn annulus_holes_are_subtracted() {
+        let annulus = Annulus {
+            center: Vec2::new(1.0, 1.0),
+            inner_radius: 3.0,
+            outer_radius: 5.0,
+        };
+
+        assert_near(area(&annulus, TOLERANCE), 16.0 * PI, 16.0 * PI * 1e-3);
+        assert_near(perimeter(&annulus, TOLERANCE), 16.0 * PI, 16.0 * PI * 1e-3);
+        let centroid = centroid(&annulus, TOLERANCE).unwrap();
+        assert_near(centroid.x, 1.0, 1e-3);
+        assert_near(centroid.y, 1.0, 1e-3);
+    }
+
+    #[test]
+    fn lines_and_empty_geometries() {
+        let mut builder = Path::builder();
+        builder.begin(Point::new(0.0, 0.0));
+        builder.line_to(Point::new(4.0, 0.0));
+        builder.end(false);
+        let line = builder.build();
+
+        assert_near(area(&line, TOLERANCE), 0.0, 1e-6);
+        assert_near(perimeter(&line, TOLERANCE), 4.0, 1e-6);
+        assert_eq!(centroid(&line, TOLERANCE), Some(Vec2::new(2.0, 0.0)));
+
+        let empty = Path::new();
+        assert_eq!(bounds(&empty), None);
+        assert_eq!(centroid(&empty, TOLERANCE), None);
+        assert_near(area(&empty, TOLERANCE), 0.0, 0.0);
+    }
+}
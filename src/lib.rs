@@ -26,21 +26,49 @@
 // Could have many false positives. Uncomment if needed.
 //#![allow(clippy::must_use_candidate)]
 
+pub mod batch;
+pub mod boolean;
+pub mod cache;
+#[cfg(feature = "collider")]
+pub mod collider;
+pub mod debug;
 pub mod entity;
 pub mod geometry;
+mod measure;
+pub mod paint;
 pub mod path;
+pub mod picking;
 pub mod plugin;
+pub mod render;
 pub mod shapes;
+pub mod stroke;
+pub mod svg;
+#[cfg(feature = "svg")]
+pub mod svg_asset;
+mod tessellation;
 pub mod utils;
 
 /// Import this module as `use bevy_prototype_lyon::prelude::*` to get
 /// convenient imports.
 pub mod prelude {
+    #[cfg(feature = "collider")]
+    pub use crate::collider::{ColliderData, ColliderKind, ColliderOptions, ShapeCollider};
+    #[cfg(feature = "svg")]
+    pub use crate::svg_asset::{Svg, SvgBundle};
     pub use crate::{
-        geometry::{Geometry, GeometryBuilder},
+        batch::Batched,
+        debug::ShapeDebugDraw,
+        entity::{FailedTessellation, OutlineMaterial, ShapeBundle},
+        geometry::{Geometry, GeometryBuilder, ShapeBounds, Transformed},
+        paint::{
+            FillUvMapping, Gradient, GradientStop, ShapePaint, SpreadMethod, StrokeUvMapping,
+            UvMapping,
+        },
         path::PathBuilder,
-        plugin::ShapePlugin,
+        picking::{Pickable, PickingCamera, PickingEvent, ShapePickingPlugin},
+        plugin::{ShapePlugin, ShapeTessellationError, TessellationSettings},
         shapes,
+        stroke::{StrokeDash, StrokeTrim},
         utils::TessellationMode,
     };
     pub use lyon_tessellation::{
@@ -0,0 +1,415 @@
+//! Measurement of paths: their arc length, used to extract parts of them, and
+//! the polygons they are flattened into, used to compute their area and to
+//! test which points they contain.
+
+use lyon_tessellation::{
+    geom::{self, CubicBezierSegment, LineSegment, QuadraticBezierSegment},
+    math::Point,
+    path::{
+        iterator::PathIterator, path::BuilderWithAttributes, AttributeStore, Event, Path, PathEvent,
+    },
+    FillRule,
+};
+use std::{iter, ops::Range};
+
+/// A segment of a path.
+#[derive(Debug, Clone, Copy)]
+enum Curve {
+    Line(LineSegment<f32>),
+    Quadratic(QuadraticBezierSegment<f32>),
+    Cubic(CubicBezierSegment<f32>),
+}
+
+impl Curve {
+    /// Returns the curve of a segment event, with the attributes of its
+    /// endpoints.
+    const fn from_event<'a>(
+        event: &Event<(Point, &'a [f32]), Point>,
+    ) -> Option<(Self, &'a [f32], &'a [f32])> {
+        match *event {
+            Event::Line {
+                from: (from, from_attributes),
+                to: (to, to_attributes),
+            } => Some((
+                Self::Line(LineSegment { from, to }),
+                from_attributes,
+                to_attributes,
+            )),
+            Event::Quadratic {
+                from: (from, from_attributes),
+                ctrl,
+                to: (to, to_attributes),
+            } => Some((
+                Self::Quadratic(QuadraticBezierSegment { from, ctrl, to }),
+                from_attributes,
+                to_attributes,
+            )),
+            Event::Cubic {
+                from: (from, from_attributes),
+                ctrl1,
+                ctrl2,
+                to: (to, to_attributes),
+            } => Some((
+                Self::Cubic(CubicBezierSegment {
+                    from,
+                    ctrl1,
+                    ctrl2,
+                    to,
+                }),
+                from_attributes,
+                to_attributes,
+            )),
+            Event::Begin { .. } | Event::End { .. } => None,
+        }
+    }
+
+    fn sample(&self, t: f32) -> Point {
+        match self {
+            Self::Line(line) => line.sample(t),
+            Self::Quadratic(curve) => curve.sample(t),
+            Self::Cubic(curve) => curve.sample(t),
+        }
+    }
+
+    fn split_range(&self, t_range: Range<f32>) -> Self {
+        match self {
+            Self::Line(line) => Self::Line(line.split_range(t_range)),
+            Self::Quadratic(curve) => Self::Quadratic(curve.split_range(t_range)),
+            Self::Cubic(curve) => Self::Cubic(curve.split_range(t_range)),
+        }
+    }
+
+    /// Calls `callback` with the points and curve parameters of the flattened
+    /// curve, starting after its first point.
+    fn for_each_flattened_with_t(&self, tolerance: f32, callback: &mut impl FnMut(Point, f32)) {
+        match self {
+            Self::Line(line) => callback(line.to, 1.0),
+            Self::Quadratic(curve) => curve.for_each_flattened_with_t(tolerance, callback),
+            Self::Cubic(curve) => curve.for_each_flattened_with_t(tolerance, callback),
+        }
+    }
+
+    /// Appends the curve to a sub-path that ends at its first point.
+    fn append(&self, builder: &mut BuilderWithAttributes, attributes: &[f32]) {
+        match *self {
+            Self::Line(line) => {
+                builder.line_to(line.to, attributes);
+            }
+            Self::Quadratic(curve) => {
+                builder.quadratic_bezier_to(curve.ctrl, curve.to, attributes);
+            }
+            Self::Cubic(curve) => {
+                builder.cubic_bezier_to(curve.ctrl1, curve.ctrl2, curve.to, attributes);
+            }
+        }
+    }
+}
+
+/// A segment of a sub-path, with its arc length parametrization.
+#[derive(Debug, Clone)]
+struct Segment {
+    curve: Curve,
+    from_attributes: Vec<f32>,
+    to_attributes: Vec<f32>,
+    /// Pairs of curve parameter and distance from the beginning of the
+    /// sub-path, both increasing.
+    samples: Vec<(f32, f32)>,
+}
+
+impl Segment {
+    fn new(
+        curve: Curve,
+        from_attributes: &[f32],
+        to_attributes: &[f32],
+        start: f32,
+        tolerance: f32,
+    ) -> Self {
+        let mut samples = vec![(0.0, start)];
+        let mut previous = curve.sample(0.0);
+        let mut distance = start;
+        curve.for_each_flattened_with_t(tolerance, &mut |point, t| {
+            distance += (point - previous).length();
+            previous = point;
+            samples.push((t, distance));
+        });
+
+        Self {
+            curve,
+            from_attributes: from_attributes.to_vec(),
+            to_attributes: to_attributes.to_vec(),
+            samples,
+        }
+    }
+
+    /// The distance from the beginning of the sub-path to the start of the
+    /// segment.
+    fn start(&self) -> f32 {
+        self.samples[0].1
+    }
+
+    /// The distance from the beginning of the sub-path to the end of the
+    /// segment.
+    fn end(&self) -> f32 {
+        self.samples[self.samples.len() - 1].1
+    }
+
+    /// Returns the curve parameter at the given distance from the beginning
+    /// of the sub-path.
+    fn t_at(&self, distance: f32) -> f32 {
+        let next = match self.samples.iter().position(|&(_, d)| d >= distance) {
+            Some(0) => return 0.0,
+            Some(next) => next,
+            None => return 1.0,
+        };
+        let ((t0, d0), (t1, d1)) = (self.samples[next - 1], self.samples[next]);
+        if d1 > d0 {
+            t0 + (t1 - t0) * (distance - d0) / (d1 - d0)
+        } else {
+            t1
+        }
+    }
+
+    /// Interpolates the attributes of the endpoints at the curve parameter
+    /// `t`, followed by the `extra` attributes.
+    fn attributes_at(&self, t: f32, extra: &[f32], attributes: &mut Vec<f32>) {
+        attributes.clear();
+        attributes.extend(
+            self.from_attributes
+                .iter()
+                .zip(&self.to_attributes)
+                .map(|(from, to)| from + (to - from) * t),
+        );
+        attributes.extend_from_slice(extra);
+    }
+}
+
+/// A sub-path measured by arc length.
+#[derive(Debug, Clone)]
+pub(crate) struct SubPath {
+    start: Point,
+    start_attributes: Vec<f32>,
+    segments: Vec<Segment>,
+    closed: bool,
+    length: f32,
+}
+
+impl SubPath {
+    /// Starts an empty sub-path at the given point.
+    fn begin(start: Point, attributes: &[f32]) -> Self {
+        Self {
+            start,
+            start_attributes: attributes.to_vec(),
+            segments: Vec::new(),
+            closed: false,
+            length: 0.0,
+        }
+    }
+
+    /// Measures a curve starting at the end of the sub-path, and appends it.
+    fn push(&mut self, curve: Curve, from: &[f32], to: &[f32], tolerance: f32) {
+        let segment = Segment::new(curve, from, to, self.length, tolerance);
+        self.length = segment.end();
+        self.segments.push(segment);
+    }
+
+    /// The arc length of the sub-path.
+    pub(crate) const fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// Returns the distances from the beginning of the sub-path to its
+    /// endpoints, in order, followed by its length if it is closed by a line.
+    pub(crate) fn endpoint_distances(&self) -> impl Iterator<Item = f32> + '_ {
+        iter::once(0.0).chain(self.segments.iter().map(Segment::end))
+    }
+
+    /// Returns the point at the given distance from the beginning of the
+    /// sub-path, clamped to its ends.
+    pub(crate) fn point_at(&self, distance: f32) -> Point {
+        self.segments
+            .iter()
+            .find(|segment| segment.end() >= distance)
+            .or_else(|| self.segments.last())
+            .map_or(self.start, |segment| {
+                segment.curve.sample(segment.t_at(distance))
+            })
+    }
+
+    /// Appends to `builder` the part of the sub-path between the distances
+    /// `range.start` and `range.end` from its beginning, as a new sub-path.
+    ///
+    /// The `extra` attributes are added after the attributes of every
+    /// endpoint, so `builder` must have as many more attributes than the path.
+    ///
+    /// Nothing is appended if the range doesn't intersect the sub-path. A
+    /// closed sub-path stays closed if the range covers it entirely.
+    pub(crate) fn append_range(
+        &self,
+        builder: &mut BuilderWithAttributes,
+        range: Range<f32>,
+        extra: &[f32],
+    ) {
+        let (start, end) = (range.start.max(0.0), range.end.min(self.length));
+        if start > end {
+            return;
+        }
+        if start <= 0.0 && end >= self.length && (self.closed || self.segments.is_empty()) {
+            self.append_whole(builder, extra);
+            return;
+        }
+
+        let mut attributes = Vec::with_capacity(self.start_attributes.len() + extra.len());
+        let mut begun = false;
+        for segment in &self.segments {
+            if segment.end() < start {
+                continue;
+            }
+            if segment.start() > end {
+                break;
+            }
+
+            let t0 = segment.t_at(start);
+            let t1 = segment.t_at(end);
+            if !begun {
+                segment.attributes_at(t0, extra, &mut attributes);
+                builder.begin(segment.curve.sample(t0), &attributes);
+                begun = true;
+            }
+            if t1 <= t0 {
+                continue;
+            }
+            segment.attributes_at(t1, extra, &mut attributes);
+            segment
+                .curve
+                .split_range(t0..t1)
+                .append(builder, &attributes);
+        }
+        if begun {
+            builder.end(false);
+        }
+    }
+
+    /// Appends the whole sub-path to `builder`, with the `extra` attributes.
+    fn append_whole(&self, builder: &mut BuilderWithAttributes, extra: &[f32]) {
+        let mut attributes = [&self.start_attributes[..], extra].concat();
+        builder.begin(self.start, &attributes);
+        for segment in &self.segments {
+            attributes.clear();
+            attributes.extend_from_slice(&segment.to_attributes);
+            attributes.extend_from_slice(extra);
+            segment.curve.append(builder, &attributes);
+        }
+        builder.end(self.closed);
+    }
+}
+
+/// A path measured by arc length.
+#[derive(Debug, Clone)]
+pub(crate) struct MeasuredPath {
+    pub(crate) sub_paths: Vec<SubPath>,
+    pub(crate) num_attributes: usize,
+}
+
+impl MeasuredPath {
+    /// Measures the path, approximating its curves by line segments within
+    /// `tolerance`.
+    pub(crate) fn new(path: &Path, tolerance: f32) -> Self {
+        let mut sub_paths = Vec::new();
+        let mut current: Option<SubPath> = None;
+        for event in path.iter_with_attributes() {
+            match event {
+                Event::Begin {
+                    at: (at, attributes),
+                } => current = Some(SubPath::begin(at, attributes)),
+                Event::End {
+                    last: (last, last_attributes),
+                    first: (first, first_attributes),
+                    close,
+                } => {
+                    if let Some(mut sub_path) = current.take() {
+                        if close && last != first {
+                            let closing = Curve::Line(LineSegment {
+                                from: last,
+                                to: first,
+                            });
+                            sub_path.push(closing, last_attributes, first_attributes, tolerance);
+                        }
+                        sub_path.closed = close;
+                        sub_paths.push(sub_path);
+                    }
+                }
+                event => {
+                    if let (Some(sub_path), Some((curve, from, to))) =
+                        (current.as_mut(), Curve::from_event(&event))
+                    {
+                        sub_path.push(curve, from, to, tolerance);
+                    }
+                }
+            }
+        }
+
+        Self {
+            sub_paths,
+            num_attributes: path.num_attributes(),
+        }
+    }
+
+    /// The arc length of all the sub-paths.
+    pub(crate) fn length(&self) -> f32 {
+        self.sub_paths.iter().map(SubPath::length).sum()
+    }
+}
+
+/// A straight edge of a flattened path.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Edge {
+    pub(crate) from: Point,
+    pub(crate) to: Point,
+}
+
+/// Returns the edges of the flattened path, closing its open sub-paths.
+pub(crate) fn flatten(path: &Path, tolerance: f32) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    let mut push = |from: Point, to: Point| {
+        if from != to {
+            edges.push(Edge { from, to });
+        }
+    };
+    for event in path.iter().flattened(tolerance) {
+        match event {
+            PathEvent::Line { from, to } => push(from, to),
+            PathEvent::End { last, first, .. } => push(last, first),
+            _ => {}
+        }
+    }
+
+    edges
+}
+
+/// Returns the winding number of the closed polygons formed by the edges
+/// around the point.
+pub(crate) fn winding_number(point: geom::Point<f64>, edges: &[Edge]) -> i32 {
+    let mut winding = 0;
+    for edge in edges {
+        let (from, to) = (edge.from.to_f64(), edge.to.to_f64());
+        let side = (to - from).cross(point - from);
+        if from.y <= point.y {
+            if to.y > point.y && side > 0.0 {
+                winding += 1;
+            }
+        } else if to.y <= point.y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+
+    winding
+}
+
+/// Returns `true` if a point of the given winding number is inside of the
+/// area determined by `fill_rule`.
+pub(crate) const fn is_filled(winding: i32, fill_rule: FillRule) -> bool {
+    match fill_rule {
+        FillRule::EvenOdd => winding % 2 != 0,
+        FillRule::NonZero => winding != 0,
+    }
+}
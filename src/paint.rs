@@ -0,0 +1,207 @@
+//! Gradient paints and texture mapping for shapes.
+//!
+//! A [`ShapePaint`] component colors a shape with a [`Gradient`]. The
+//! gradient is evaluated by the shape pipeline for every pixel, from its
+//! position in the coordinate system of the path, so it does not depend on
+//! how finely the shape is tessellated, and changing it does not tessellate
+//! the shape again. The pipeline draws at most
+//! [`MAX_GRADIENT_STOPS`](crate::render::MAX_GRADIENT_STOPS) stops.
+//!
+//! The [`UvMapping`] component controls how a texture is laid over the shape.
+
+use bevy::{math::Vec2, render::color::Color};
+
+/// The shape of a [`Gradient`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    /// The colors vary along the line going from `start` (offset 0) to `end`
+    /// (offset 1).
+    Linear {
+        /// The point where the offset is 0.
+        start: Vec2,
+        /// The point where the offset is 1.
+        end: Vec2,
+    },
+    /// The colors vary along the distance from `center` (offset 0) to the
+    /// circle of the given `radius` (offset 1).
+    Radial {
+        /// The point where the offset is 0.
+        center: Vec2,
+        /// The distance from the center where the offset is 1.
+        radius: f32,
+    },
+}
+
+/// What happens to the offsets outside of the `0..=1` range of a
+/// [`Gradient`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpreadMethod {
+    /// The colors of the first and last stops extend indefinitely.
+    Pad,
+    /// The gradient starts over.
+    Repeat,
+    /// The gradient starts over in the opposite direction, back and forth.
+    Reflect,
+}
+
+impl Default for SpreadMethod {
+    fn default() -> Self {
+        Self::Pad
+    }
+}
+
+/// A color at a given offset of a [`Gradient`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    /// The offset of the stop, usually between 0 and 1.
+    pub offset: f32,
+    /// The color of the gradient at the offset of the stop.
+    pub color: Color,
+}
+
+impl GradientStop {
+    /// Creates a new gradient stop.
+    #[must_use]
+    pub const fn new(offset: f32, color: Color) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// A gradual transition between colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    /// The shape of the gradient.
+    pub kind: GradientKind,
+    /// The color stops, sorted by offset. The colors are interpolated in the
+    /// linear RGB space.
+    pub stops: Vec<GradientStop>,
+    /// What happens outside of the `0..=1` offset range.
+    pub spread: SpreadMethod,
+}
+
+impl Gradient {
+    /// Creates a linear gradient going from `start` to `end`, with the
+    /// [`Pad`](SpreadMethod::Pad) spread method.
+    #[must_use]
+    pub fn linear(start: Vec2, end: Vec2, stops: Vec<GradientStop>) -> Self {
+        Self {
+            kind: GradientKind::Linear { start, end },
+            stops,
+            spread: SpreadMethod::default(),
+        }
+    }
+
+    /// Creates a radial gradient centered on `center`, with the
+    /// [`Pad`](SpreadMethod::Pad) spread method.
+    #[must_use]
+    pub fn radial(center: Vec2, radius: f32, stops: Vec<GradientStop>) -> Self {
+        Self {
+            kind: GradientKind::Radial { center, radius },
+            stops,
+            spread: SpreadMethod::default(),
+        }
+    }
+
+    /// Sets the spread method of the gradient.
+    #[must_use]
+    pub fn with_spread(mut self, spread: SpreadMethod) -> Self {
+        self.spread = spread;
+        self
+    }
+}
+
+/// Component that paints the geometry of a shape with gradients.
+///
+/// The fill gradient colors the geometry produced by
+/// [`TessellationMode::Fill`](crate::utils::TessellationMode::Fill), and the
+/// stroke gradient the one produced by
+/// [`TessellationMode::Stroke`](crate::utils::TessellationMode::Stroke). Both
+/// are used with
+/// [`TessellationMode::FillAndStroke`](crate::utils::TessellationMode::FillAndStroke).
+/// The gradient colors are multiplied by the material color and by the vertex
+/// colors of the path, if any.
+///
+/// The gradients are copied into the
+/// [`ShapeGradient`](crate::render::ShapeGradient) components drawn by the
+/// shape pipeline.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShapePaint {
+    /// The gradient of the fill geometry, or `None` to keep it uniform.
+    pub fill: Option<Gradient>,
+    /// The gradient of the stroke geometry, or `None` to keep it uniform.
+    pub stroke: Option<Gradient>,
+}
+
+impl ShapePaint {
+    /// Paints the fill geometry with the given gradient.
+    #[must_use]
+    pub fn fill(gradient: Gradient) -> Self {
+        Self {
+            fill: Some(gradient),
+            stroke: None,
+        }
+    }
+
+    /// Paints the stroke geometry with the given gradient.
+    #[must_use]
+    pub fn stroke(gradient: Gradient) -> Self {
+        Self {
+            fill: None,
+            stroke: Some(gradient),
+        }
+    }
+}
+
+/// How the texture coordinates of the fill geometry are generated from the
+/// bounding box of the path.
+///
+/// In texture space, `v` grows downwards, so textures appear upright.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillUvMapping {
+    /// The texture is stretched over the bounding box.
+    Stretch,
+    /// The texture is scaled uniformly, so that it spans the longest side of
+    /// the bounding box, and it is centered on it.
+    PreserveAspect,
+    /// The texture is repeated every `size` world units, starting from the
+    /// origin of the path.
+    ///
+    /// The texture must be sampled with the `Repeat` address mode.
+    Tile(Vec2),
+}
+
+/// How the texture coordinates of the stroke geometry are generated.
+///
+/// The `v` coordinate always goes from 0 on the left side of the stroke to 1
+/// on its right side, while `u` grows along the path.
+///
+/// The dashes and the trimmed part of a stroke keep the coordinates they have
+/// in the full stroke, so the texture doesn't slide as the trim is animated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StrokeUvMapping {
+    /// `u` goes from 0 at the start to 1 at the end of the longest sub-path.
+    Stretch,
+    /// The texture is repeated every given length along the path.
+    ///
+    /// The texture must be sampled with the `Repeat` address mode.
+    Tile(f32),
+}
+
+/// Component that controls the texture coordinates of the vertices of a shape,
+/// used by a [`ColorMaterial`](bevy::sprite::ColorMaterial) with a texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvMapping {
+    /// The mapping of the fill geometry.
+    pub fill: FillUvMapping,
+    /// The mapping of the stroke geometry.
+    pub stroke: StrokeUvMapping,
+}
+
+impl Default for UvMapping {
+    fn default() -> Self {
+        Self {
+            fill: FillUvMapping::Stretch,
+            stroke: StrokeUvMapping::Stretch,
+        }
+    }
+}
@@ -1,58 +1,174 @@
 //! Interface to build custom paths.
 
-use crate::utils::Convert;
-use bevy::math::Vec2;
+use crate::{
+    measure::MeasuredPath,
+    svg::{parse_path_data, SvgPathError},
+    utils::Convert,
+};
+use bevy::{math::Vec2, utils::HashMap};
 use lyon_tessellation::{
     geom::Angle,
-    path::{builder::WithSvg, path::Builder, EndpointId, Path},
+    path::{
+        builder::WithSvg,
+        path::{Builder, BuilderWithAttributes},
+        EndpointId, IdEvent, Path,
+    },
+    StrokeOptions,
 };
 
+/// The number of custom attributes of the paths with vertex colors: the linear
+/// RGBA color of each endpoint.
+pub(crate) const COLOR_ATTRIBUTES: usize = 4;
+/// The index of the stroke width in the custom attributes of the paths with
+/// stroke widths, where it follows the vertex color.
+pub(crate) const WIDTH_ATTRIBUTE: usize = COLOR_ATTRIBUTES;
+/// The width of the endpoints of the sub-paths without any width, that are
+/// stroked with the line width of the [`StrokeOptions`].
+///
+/// [`StrokeOptions`]: lyon_tessellation::StrokeOptions
+pub(crate) const NO_WIDTH: f32 = -1.0;
+
 /// A SVG-like path builder.
-pub struct PathBuilder(WithSvg<Builder>);
+pub struct PathBuilder {
+    builder: WithSvg<Builder>,
+    /// The stroke widths given to endpoints with
+    /// [`set_width`](Self::set_width).
+    widths: Vec<(EndpointId, f32)>,
+}
 
 impl PathBuilder {
     /// Returns a new, empty `PathBuilder`.
     #[must_use]
     pub fn new() -> Self {
-        Self(Builder::new().with_svg())
+        Self {
+            builder: Builder::new().with_svg(),
+            widths: Vec::new(),
+        }
+    }
+
+    /// Returns a new `PathBuilder` containing the path described by a SVG path
+    /// data string, like the content of the `d` attribute of a `<path>`
+    /// element.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SvgPathError`] with the byte offset of the first malformed
+    /// part of the string.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy_prototype_lyon::prelude::*;
+    ///
+    /// let heart = PathBuilder::from_svg_path(
+    ///     "M 10,30 A 20,20 0,0,1 50,30 A 20,20 0,0,1 90,30 Q 90,60 50,90 Q 10,60 10,30 z",
+    /// )
+    /// .unwrap()
+    /// .build();
+    /// ```
+    pub fn from_svg_path(data: &str) -> Result<Self, SvgPathError> {
+        let mut builder = Self::new();
+        parse_path_data(data, &mut builder.builder)?;
+
+        Ok(builder)
     }
 
     /// Returns a finalized [`Path`].
+    ///
+    /// If widths have been given to endpoints, they are stored in the custom
+    /// attributes of the path.
     #[must_use]
     pub fn build(self) -> Path {
-        self.0.build()
+        let path = self.builder.build();
+        if self.widths.is_empty() {
+            return path;
+        }
+
+        let widths = self.widths.into_iter().collect::<HashMap<_, _>>();
+        let measured = MeasuredPath::new(&path, StrokeOptions::DEFAULT_TOLERANCE);
+        let mut measured_sub_paths = measured.sub_paths.iter();
+        let mut builder = Path::builder_with_attributes(WIDTH_ATTRIBUTE + 1);
+        let mut sub_path = Vec::new();
+        for event in path.id_iter() {
+            sub_path.push(event);
+            if let IdEvent::End { .. } = event {
+                let distances = measured_sub_paths
+                    .next()
+                    .expect("every sub-path is measured")
+                    .endpoint_distances();
+                add_sub_path_with_widths(&mut builder, &path, &sub_path, distances, &widths);
+                sub_path.clear();
+            }
+        }
+
+        builder.build()
+    }
+
+    /// Sets the width of the stroke at an endpoint, as returned by the other
+    /// methods.
+    ///
+    /// The width is interpolated by arc length between the endpoints that
+    /// have one, and extended to the ends of their sub-path. It replaces the
+    /// line width of the [`StrokeOptions`], which should be set to the largest
+    /// width since it still determines the number of vertices of round joins
+    /// and caps. The sub-paths without any width are stroked with the line
+    /// width.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_prototype_lyon::prelude::*;
+    ///
+    /// // A tapered brush stroke.
+    /// let mut builder = PathBuilder::new();
+    /// let start = builder.move_to(Vec2::zero());
+    /// builder.set_width(start, 8.0);
+    /// let end = builder.cubic_bezier_to(
+    ///     Vec2::new(50.0, 50.0),
+    ///     Vec2::new(100.0, -50.0),
+    ///     Vec2::new(150.0, 0.0),
+    /// );
+    /// builder.set_width(end, 0.0);
+    /// let path = builder.build();
+    /// ```
+    ///
+    /// [`StrokeOptions`]: lyon_tessellation::StrokeOptions
+    pub fn set_width(&mut self, endpoint: EndpointId, width: f32) {
+        self.widths.push((endpoint, width.max(0.0)));
     }
 
     /// Moves the current point to the given position.
     pub fn move_to(&mut self, to: Vec2) -> EndpointId {
-        self.0.move_to(to.convert())
+        self.builder.move_to(to.convert())
     }
 
     /// Adds to the path a line from the current position to the given one.
     pub fn line_to(&mut self, to: Vec2) -> EndpointId {
-        self.0.line_to(to.convert())
+        self.builder.line_to(to.convert())
     }
 
     /// Closes the shape, adding to the path a line from the current position to
     /// the starting location.
     pub fn close(&mut self) {
-        self.0.close();
+        self.builder.close();
     }
 
     /// Adds a quadratic bezier to the path.
     pub fn quadratic_bezier_to(&mut self, ctrl: Vec2, to: Vec2) -> EndpointId {
-        self.0.quadratic_bezier_to(ctrl.convert(), to.convert())
+        self.builder
+            .quadratic_bezier_to(ctrl.convert(), to.convert())
     }
 
     /// Adds a cubic bezier to the path.
     pub fn cubic_bezier_to(&mut self, ctrl1: Vec2, ctrl2: Vec2, to: Vec2) -> EndpointId {
-        self.0
+        self.builder
             .cubic_bezier_to(ctrl1.convert(), ctrl2.convert(), to.convert())
     }
 
     /// Adds an arc to the path.
     pub fn arc(&mut self, center: Vec2, radii: Vec2, sweep_angle: f32, x_rotation: f32) {
-        self.0.arc(
+        self.builder.arc(
             center.convert(),
             radii.convert(),
             Angle::radians(sweep_angle),
@@ -63,7 +179,7 @@ impl PathBuilder {
     /// Returns the path's current position.
     #[must_use]
     pub fn current_position(&self) -> Vec2 {
-        let p = self.0.current_position();
+        let p = self.builder.current_position();
         Vec2::new(p.x, p.y)
     }
 }
@@ -73,3 +189,120 @@ impl Default for PathBuilder {
         Self::new()
     }
 }
+
+/// Adds a sub-path of `path`, given by its events, to `builder` with the
+/// interpolated stroke widths of its endpoints.
+///
+/// The widths are interpolated by the `distances` along the sub-path of its
+/// endpoints.
+fn add_sub_path_with_widths(
+    builder: &mut BuilderWithAttributes,
+    path: &Path,
+    events: &[IdEvent],
+    distances: impl Iterator<Item = f32>,
+    widths: &HashMap<EndpointId, f32>,
+) {
+    let endpoints = events.iter().filter_map(|event| match *event {
+        IdEvent::Begin { at } => Some(at),
+        IdEvent::Line { to, .. } | IdEvent::Quadratic { to, .. } | IdEvent::Cubic { to, .. } => {
+            Some(to)
+        }
+        IdEvent::End { .. } => None,
+    });
+    let endpoints = endpoints.zip(distances).collect::<Vec<_>>();
+    let known = endpoints
+        .iter()
+        .filter_map(|&(endpoint, distance)| widths.get(&endpoint).map(|&width| (distance, width)))
+        .collect::<Vec<_>>();
+
+    let mut attributes = endpoints
+        .iter()
+        .map(|&(_, distance)| [1.0, 1.0, 1.0, 1.0, width_at(&known, distance)]);
+    let mut next = || attributes.next().expect("every endpoint has attributes");
+    for event in events {
+        match *event {
+            IdEvent::Begin { at } => {
+                builder.begin(path[at], &next());
+            }
+            IdEvent::Line { to, .. } => {
+                builder.line_to(path[to], &next());
+            }
+            IdEvent::Quadratic { ctrl, to, .. } => {
+                builder.quadratic_bezier_to(path[ctrl], path[to], &next());
+            }
+            IdEvent::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => {
+                builder.cubic_bezier_to(path[ctrl1], path[ctrl2], path[to], &next());
+            }
+            IdEvent::End { close, .. } => builder.end(close),
+        }
+    }
+}
+
+/// Interpolates the `known` pairs of distance and width, sorted by distance,
+/// at the given distance, or returns [`NO_WIDTH`] if there are none.
+fn width_at(known: &[(f32, f32)], distance: f32) -> f32 {
+    if known.is_empty() {
+        return NO_WIDTH;
+    }
+    match known.iter().position(|&(d, _)| d >= distance) {
+        Some(0) => known[0].1,
+        None => known[known.len() - 1].1,
+        Some(next) => {
+            let ((d0, w0), (d1, w1)) = (known[next - 1], known[next]);
+            if d1 > d0 {
+                w0 + (w1 - w0) * (distance - d0) / (d1 - d0)
+            } else {
+                w1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lyon_tessellation::path::Event;
+
+    /// Returns the stroke width of every endpoint of the path, in order.
+    fn widths(path: &Path) -> Vec<f32> {
+        path.iter_with_attributes()
+            .filter_map(|event| match event {
+                Event::Begin { at: (_, at) } => Some(at[WIDTH_ATTRIBUTE]),
+                Event::Line { to: (_, to), .. }
+                | Event::Quadratic { to: (_, to), .. }
+                | Event::Cubic { to: (_, to), .. } => Some(to[WIDTH_ATTRIBUTE]),
+                Event::End { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn widths_are_interpolated_by_arc_length() {
+        let mut builder = PathBuilder::new();
+        let start = builder.move_to(Vec2::new(0.0, 0.0));
+        builder.set_width(start, 0.0);
+        builder.quadratic_bezier_to(Vec2::new(10.0, 20.0), Vec2::new(20.0, 0.0));
+        let end = builder.line_to(Vec2::new(30.0, 0.0));
+        builder.set_width(end, 10.0);
+
+        // The curve is about 29.58 long, while its chord is 20 long.
+        let widths = widths(&builder.build());
+        assert_eq!(widths.len(), 3);
+        assert!((widths[1] - 10.0 * 29.58 / 39.58).abs() < 0.05);
+        assert!((widths[2] - 10.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn sub_paths_without_width_use_the_line_width() {
+        let mut builder = PathBuilder::new();
+        let start = builder.move_to(Vec2::new(0.0, 0.0));
+        builder.set_width(start, 4.0);
+        builder.line_to(Vec2::new(10.0, 0.0));
+        builder.move_to(Vec2::new(0.0, 10.0));
+        builder.line_to(Vec2::new(10.0, 10.0));
+
+        assert_eq!(widths(&builder.build()), vec![4.0, 4.0, NO_WIDTH, NO_WIDTH]);
+    }
+}
@@ -0,0 +1,445 @@
+//! Hit testing of shapes, and picking of the shapes under the cursor.
+//!
+//! [`hit_test`] and [`hit_test_global`] check whether a point is inside of the
+//! geometry of a shape. The [`ShapePickingPlugin`] uses them to send
+//! [`PickingEvent`]s when the cursor hovers or clicks the [`Pickable`] shapes.
+
+use crate::{
+    geometry::ShapeBounds,
+    measure::{flatten, is_filled, winding_number},
+    utils::{Convert, TessellationMode},
+};
+use bevy::{
+    app::{AppBuilder, Events, Plugin},
+    ecs::{Entity, IntoSystem, Local, Query, Res, ResMut, With},
+    input::{mouse::MouseButton, Input},
+    math::Vec2,
+    render::{camera::Camera, draw::Visible},
+    transform::components::GlobalTransform,
+    window::Windows,
+};
+use lyon_tessellation::{
+    geom,
+    math::Point,
+    path::{iterator::PathIterator, Path, PathEvent},
+    FillOptions, StrokeOptions,
+};
+use std::cmp::Ordering;
+
+/// Returns `true` if the point is inside of the geometry drawn for the path
+/// with the given tessellation mode.
+///
+/// The point is in the coordinate system of the path. The fill is tested with
+/// the fill rule of the [`FillOptions`], and the stroke with the line width of
+/// the [`StrokeOptions`], as if it had round joins and caps. The curves are
+/// flattened with the tolerance of the options.
+#[must_use]
+pub fn hit_test(path: &Path, mode: &TessellationMode, point: Vec2) -> bool {
+    match mode {
+        TessellationMode::Fill(options) => fill_contains(path, options, point),
+        TessellationMode::Stroke(options) => stroke_contains(path, options, point),
+        TessellationMode::FillAndStroke(fill_options, stroke_options) => {
+            fill_contains(path, fill_options, point) || stroke_contains(path, stroke_options, point)
+        }
+    }
+}
+
+/// Returns `true` if the point, in world coordinates, is inside of the
+/// geometry of a shape with the given [`GlobalTransform`].
+///
+/// The point is brought into the coordinate system of the path along the z
+/// axis, and tested with [`hit_test`].
+#[must_use]
+pub fn hit_test_global(
+    path: &Path,
+    mode: &TessellationMode,
+    transform: &GlobalTransform,
+    point: Vec2,
+) -> bool {
+    let matrix = transform.compute_matrix();
+    if matrix.determinant() == 0.0 {
+        return false;
+    }
+    let local = matrix.inverse().transform_point3(point.extend(0.0));
+
+    hit_test(path, mode, local.truncate())
+}
+
+fn fill_contains(path: &Path, options: &FillOptions, point: Vec2) -> bool {
+    let edges = flatten(path, options.tolerance);
+    let point = geom::point(f64::from(point.x), f64::from(point.y));
+
+    is_filled(winding_number(point, &edges), options.fill_rule)
+}
+
+fn stroke_contains(path: &Path, options: &StrokeOptions, point: Vec2) -> bool {
+    let half_width = options.line_width / 2.0;
+    let point: Point = point.convert();
+
+    path.iter()
+        .flattened(options.tolerance)
+        .any(|event| match event {
+            PathEvent::Begin { at } => (at - point).length() <= half_width,
+            PathEvent::Line { from, to } => distance_to_segment(point, from, to) <= half_width,
+            PathEvent::End {
+                last,
+                first,
+                close: true,
+            } => distance_to_segment(point, last, first) <= half_width,
+            _ => false,
+        })
+}
+
+/// Returns `false` if the point, in world coordinates, is too far from the
+/// [`ShapeBounds`] of a shape to be inside of its geometry.
+///
+/// The bounds are extended by the half-width of the stroke, and transformed
+/// into an axis-aligned box in world coordinates.
+fn may_contain(
+    bounds: &ShapeBounds,
+    mode: &TessellationMode,
+    transform: &GlobalTransform,
+    point: Vec2,
+) -> bool {
+    let margin = match mode {
+        TessellationMode::Fill(_) => 0.0,
+        TessellationMode::Stroke(options) | TessellationMode::FillAndStroke(_, options) => {
+            options.line_width / 2.0
+        }
+    };
+    let margin = Vec2::splat(margin);
+    let bounds = ShapeBounds {
+        min: bounds.min - margin,
+        max: bounds.max + margin,
+    };
+
+    bounds.transformed(transform).contains(point)
+}
+
+/// Returns the distance from `point` to the line segment going from `from` to
+/// `to`.
+fn distance_to_segment(point: Point, from: Point, to: Point) -> f32 {
+    let direction = to - from;
+    let length_squared = direction.square_length();
+    let t = if length_squared > 0.0 {
+        ((point - from).dot(direction) / length_squared)
+            .max(0.0)
+            .min(1.0)
+    } else {
+        0.0
+    };
+
+    (from + direction * t - point).length()
+}
+
+/// Marker component for the shapes that can be picked by the
+/// [`ShapePickingPlugin`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pickable;
+
+/// Marker component for the cameras through which the
+/// [`ShapePickingPlugin`] looks for the shapes under the cursor.
+///
+/// It is usually added to the entity of a
+/// [`Camera2dBundle`](bevy::render::entity::Camera2dBundle).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PickingCamera;
+
+/// An event sent by the [`ShapePickingPlugin`] when the cursor interacts with
+/// a [`Pickable`] shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickingEvent {
+    /// The cursor started hovering the shape.
+    HoverStarted(Entity),
+    /// The cursor stopped hovering the shape, or another shape got above it.
+    HoverEnded(Entity),
+    /// The left mouse button has been pressed over the shape.
+    Clicked(Entity),
+}
+
+/// A plugin that sends a [`PickingEvent`] when the cursor of the window of a
+/// [`PickingCamera`] interacts with a [`Pickable`] shape.
+///
+/// Only the visible shape under the cursor with the greatest z coordinate is
+/// picked. The events are sent in the
+/// [`PRE_UPDATE`](bevy::app::stage::PRE_UPDATE) stage, using the transforms of
+/// the previous frame.
+///
+/// The path of every shape is flattened to be tested each frame. Inserting a
+/// [`ShapeBounds`] on complex shapes skips this while the cursor is outside of
+/// their bounds.
+///
+/// The plugin must be added after the default plugins, since it reads the
+/// mouse input and the windows.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_prototype_lyon::prelude::*;
+///
+/// fn highlight(
+///     mut events: Local<EventReader<PickingEvent>>,
+///     picking_events: Res<Events<PickingEvent>>,
+///     mut query: Query<&mut Transform, With<Pickable>>,
+/// ) {
+///     for event in events.iter(&picking_events) {
+///         match *event {
+///             PickingEvent::HoverStarted(entity) => {
+///                 if let Ok(mut transform) = query.get_mut(entity) {
+///                     transform.scale = Vec3::splat(1.1);
+///                 }
+///             }
+///             PickingEvent::HoverEnded(entity) => {
+///                 if let Ok(mut transform) = query.get_mut(entity) {
+///                     transform.scale = Vec3::one();
+///                 }
+///             }
+///             PickingEvent::Clicked(entity) => info!("{:?} clicked", entity),
+///         }
+///     }
+/// }
+/// ```
+pub struct ShapePickingPlugin;
+
+impl Plugin for ShapePickingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<PickingEvent>()
+            .add_system_to_stage(bevy::app::stage::PRE_UPDATE, pick_shapes.system());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn pick_shapes(
+    mut hovered: Local<Option<Entity>>,
+    windows: Res<Windows>,
+    mouse: Res<Input<MouseButton>>,
+    mut events: ResMut<Events<PickingEvent>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PickingCamera>>,
+    shapes: Query<
+        (
+            Entity,
+            &Path,
+            &TessellationMode,
+            &GlobalTransform,
+            &Visible,
+            Option<&ShapeBounds>,
+        ),
+        With<Pickable>,
+    >,
+) {
+    let cursor = cameras
+        .iter()
+        .find_map(|(camera, transform)| cursor_position(&windows, camera, transform));
+    let picked = cursor.and_then(|cursor| {
+        shapes
+            .iter()
+            .filter(|(_, path, mode, transform, visible, bounds)| {
+                visible.is_visible
+                    && bounds.map_or(true, |bounds| may_contain(bounds, mode, transform, cursor))
+                    && hit_test_global(path, mode, transform, cursor)
+            })
+            .max_by(|(.., a, _, _), (.., b, _, _)| {
+                a.translation
+                    .z
+                    .partial_cmp(&b.translation.z)
+                    .unwrap_or(Ordering::Equal)
+            })
+            .map(|(entity, ..)| entity)
+    });
+
+    if picked != *hovered {
+        if let Some(entity) = *hovered {
+            events.send(PickingEvent::HoverEnded(entity));
+        }
+        if let Some(entity) = picked {
+            events.send(PickingEvent::HoverStarted(entity));
+        }
+        *hovered = picked;
+    }
+    if let Some(entity) = picked {
+        if mouse.just_pressed(MouseButton::Left) {
+            events.send(PickingEvent::Clicked(entity));
+        }
+    }
+}
+
+/// Returns the position of the cursor in world coordinates, if it is inside
+/// of the window of the camera.
+fn cursor_position(
+    windows: &Windows,
+    camera: &Camera,
+    transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let window = windows.get(camera.window)?;
+    let cursor = window.cursor_position()?;
+    let size = Vec2::new(window.width(), window.height());
+    // From window coordinates to normalized device coordinates, then to the
+    // world.
+    let ndc = cursor / size * 2.0 - Vec2::one();
+    let matrix = transform.compute_matrix() * camera.projection_matrix.inverse();
+
+    Some(matrix.transform_point3(ndc.extend(0.0)).truncate())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::{Quat, Vec3};
+    use lyon_tessellation::{
+        math::{point, rect},
+        path::{traits::PathBuilder, Winding},
+        FillRule,
+    };
+    use std::f32::consts::FRAC_PI_2;
+
+    /// Returns a 10 by 10 square centered on the origin, with a 4 by 4 square
+    /// inside of it wound in the same direction.
+    fn square_with_hole() -> Path {
+        let mut builder = Path::builder();
+        builder.add_rectangle(&rect(-5.0, -5.0, 10.0, 10.0), Winding::Positive);
+        builder.add_rectangle(&rect(-2.0, -2.0, 4.0, 4.0), Winding::Positive);
+        builder.build()
+    }
+
+    fn fill(fill_rule: FillRule) -> FillOptions {
+        FillOptions::default().with_fill_rule(fill_rule)
+    }
+
+    fn stroke(line_width: f32) -> StrokeOptions {
+        StrokeOptions::default().with_line_width(line_width)
+    }
+
+    #[test]
+    fn fill_rules_determine_holes() {
+        let path = square_with_hole();
+        let non_zero = TessellationMode::Fill(fill(FillRule::NonZero));
+        let even_odd = TessellationMode::Fill(fill(FillRule::EvenOdd));
+
+        assert!(hit_test(&path, &non_zero, Vec2::new(0.0, 0.0)));
+        assert!(!hit_test(&path, &even_odd, Vec2::new(0.0, 0.0)));
+        for mode in &[non_zero, even_odd] {
+            assert!(hit_test(&path, mode, Vec2::new(3.5, 0.0)));
+            assert!(!hit_test(&path, mode, Vec2::new(6.0, 0.0)));
+        }
+    }
+
+    #[test]
+    fn strokes_extend_by_half_their_width() {
+        let path = square_with_hole();
+        let mode = TessellationMode::Stroke(stroke(2.0));
+
+        assert!(hit_test(&path, &mode, Vec2::new(5.9, 0.0)));
+        assert!(hit_test(&path, &mode, Vec2::new(4.1, 0.0)));
+        assert!(hit_test(&path, &mode, Vec2::new(1.5, 1.5)));
+        assert!(!hit_test(&path, &mode, Vec2::new(6.1, 0.0)));
+        assert!(!hit_test(&path, &mode, Vec2::new(3.5, 0.0)));
+        assert!(!hit_test(&path, &mode, Vec2::new(0.0, 0.0)));
+        // The corners are rounded.
+        assert!(hit_test(&path, &mode, Vec2::new(5.5, 5.5)));
+        assert!(!hit_test(&path, &mode, Vec2::new(5.8, 5.8)));
+    }
+
+    #[test]
+    fn fill_and_stroke_covers_both() {
+        let path = square_with_hole();
+        let mode = TessellationMode::FillAndStroke(fill(FillRule::EvenOdd), stroke(2.0));
+
+        assert!(hit_test(&path, &mode, Vec2::new(3.5, 0.0)));
+        assert!(hit_test(&path, &mode, Vec2::new(5.9, 0.0)));
+        assert!(hit_test(&path, &mode, Vec2::new(1.5, 0.0)));
+        assert!(!hit_test(&path, &mode, Vec2::new(0.0, 0.0)));
+        assert!(!hit_test(&path, &mode, Vec2::new(6.1, 0.0)));
+    }
+
+    #[test]
+    fn global_points_are_moved_into_the_path() {
+        // A 10 by 2 rectangle, scaled twice and turned vertical.
+        let mut builder = Path::builder();
+        builder.add_rectangle(&rect(-5.0, -1.0, 10.0, 2.0), Winding::Positive);
+        let path = builder.build();
+        let mode = TessellationMode::Fill(fill(FillRule::NonZero));
+        let transform = GlobalTransform {
+            translation: Vec3::new(100.0, 0.0, 5.0),
+            rotation: Quat::from_rotation_z(FRAC_PI_2),
+            scale: Vec3::splat(2.0),
+        };
+
+        assert!(hit_test_global(
+            &path,
+            &mode,
+            &transform,
+            Vec2::new(100.0, 8.0)
+        ));
+        assert!(hit_test_global(
+            &path,
+            &mode,
+            &transform,
+            Vec2::new(101.5, -9.0)
+        ));
+        assert!(!hit_test_global(
+            &path,
+            &mode,
+            &transform,
+            Vec2::new(100.0, 12.0)
+        ));
+        assert!(!hit_test_global(
+            &path,
+            &mode,
+            &transform,
+            Vec2::new(108.0, 0.0)
+        ));
+
+        let flat = GlobalTransform {
+            scale: Vec3::new(1.0, 0.0, 1.0),
+            ..transform
+        };
+        assert!(!hit_test_global(&path, &mode, &flat, Vec2::new(100.0, 0.0)));
+    }
+
+    #[test]
+    fn bounds_are_extended_by_the_stroke() {
+        let bounds = ShapeBounds {
+            min: Vec2::new(-5.0, -5.0),
+            max: Vec2::new(5.0, 5.0),
+        };
+        let transform = GlobalTransform::from_translation(Vec3::new(10.0, 0.0, 0.0));
+        let fill = TessellationMode::Fill(fill(FillRule::NonZero));
+        let stroke = TessellationMode::Stroke(stroke(2.0));
+
+        assert!(may_contain(
+            &bounds,
+            &fill,
+            &transform,
+            Vec2::new(14.0, 4.0)
+        ));
+        assert!(!may_contain(
+            &bounds,
+            &fill,
+            &transform,
+            Vec2::new(15.5, 0.0)
+        ));
+        assert!(may_contain(
+            &bounds,
+            &stroke,
+            &transform,
+            Vec2::new(15.5, 0.0)
+        ));
+        assert!(!may_contain(
+            &bounds,
+            &stroke,
+            &transform,
+            Vec2::new(16.5, 0.0)
+        ));
+    }
+
+    #[test]
+    fn distances_to_segments() {
+        let (from, to) = (point(0.0, 0.0), point(10.0, 0.0));
+        assert!((distance_to_segment(point(5.0, 3.0), from, to) - 3.0).abs() < 1e-6);
+        assert!((distance_to_segment(point(5.0, -3.0), from, to) - 3.0).abs() < 1e-6);
+        assert!((distance_to_segment(point(13.0, 4.0), from, to) - 5.0).abs() < 1e-6);
+        assert!((distance_to_segment(point(-3.0, -4.0), from, to) - 5.0).abs() < 1e-6);
+        assert!((distance_to_segment(point(3.0, 4.0), from, from) - 5.0).abs() < 1e-6);
+    }
+}
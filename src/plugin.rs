@@ -9,166 +9,762 @@
 //!
 //! Then, in the [`SHAPE`](stage::SHAPE) stage, there is a system
 //! that creates a mesh for each entity that has been spawned as a
-//! `ShapeBundle`.
+//! `ShapeBundle`. The mesh is regenerated every time the [`Path`], the
+//! [`TessellationMode`], the [`UvMapping`], the [`StrokeDash`] or the
+//! [`StrokeTrim`] of the entity change. If the tessellation fails, a
+//! [`ShapeTessellationError`] event is sent instead.
+//!
+//! The gradients of the [`ShapePaint`] are evaluated for every pixel by the
+//! shape pipeline. They are copied into the [`ShapeGradient`] components of the
+//! shape and of its outline in the
+//! [`POST_UPDATE`](bevy::app::stage::POST_UPDATE) stage, when they change.
+//! In the same stage, the outline entities are shown or hidden along with
+//! their shape.
+//!
+//! The [`ShapeBounds`] component of the shapes that have one is updated when
+//! their [`Path`] changes.
+//!
+//! The tessellation can be moved off the main schedule by enabling
+//! [`TessellationSettings::asynchronous`], and the meshes of identical shapes
+//! can be shared by enabling [`TessellationSettings::cache_meshes`].
+//!
+//! With the `collider` feature, the collider data of the shapes with a
+//! [`ShapeCollider`](crate::collider::ShapeCollider) component is also
+//! regenerated when their path changes.
+//!
+//! In the [`BATCH`](stage::BATCH) stage, the meshes of the
+//! [`Batched`](crate::batch::Batched) shapes are merged into a single mesh
+//! per material.
+//!
+//! The shapes drawn with the [`ShapeDebugDraw`] resource during a frame are
+//! tessellated together into the mesh of a single entity, then cleared.
+//!
+//! With the `svg` feature, the plugin also loads `.svg` files as
+//! [`Svg`](crate::svg_asset::Svg) assets and spawns the shapes of every
+//! [`SvgBundle`](crate::svg_asset::SvgBundle).
 
-use crate::{entity::Processed, utils::TessellationMode};
+#[cfg(feature = "collider")]
+use crate::collider::ShapeCollider;
+#[cfg(feature = "svg")]
+use crate::svg_asset::{spawn_svg_shapes, Svg, SvgLoader};
+use crate::{
+    batch::{batch_shapes, unbatch_shapes, update_batched_outlines},
+    cache::{MeshCache, MeshKey, ShapeMeshes},
+    debug::{draw_debug_shapes, ShapeDebugDraw},
+    entity::{FailedTessellation, Outline, OutlineMaterial},
+    geometry::{path_bounds, ShapeBounds},
+    paint::{ShapePaint, UvMapping},
+    render::{add_shape_graph, build_shape_pipeline, ShapeGradient, SHAPE_PIPELINE_HANDLE},
+    stroke::{StrokeDash, StrokeTrim},
+    tessellation::{build_mesh, tessellate, ShapeGeometry, Tessellation, VertexBuffers},
+    utils::TessellationMode,
+};
+#[cfg(feature = "svg")]
+use bevy::asset::AddAsset;
 use bevy::{
-    app::{AppBuilder, Plugin},
-    asset::{Assets, Handle},
-    ecs::{IntoSystem, Query, ResMut, SystemStage},
+    app::{AppBuilder, EventReader, Events, Plugin},
+    asset::{AssetEvent, Assets, Handle, HandleId},
+    ecs::{
+        Added, Changed, Commands, Entity, IntoSystem, Local, Or, Query, Res, ResMut, Resources,
+        SystemStage, Without,
+    },
     log::error,
+    math::{Vec2, Vec3},
     render::{
         draw::Visible,
-        mesh::{Indices, Mesh},
-        pipeline::PrimitiveTopology,
+        mesh::Mesh,
+        pipeline::{PipelineDescriptor, RenderPipeline, RenderPipelines},
+        render_graph::RenderGraph,
+        shader::Shader,
     },
+    sprite::{entity::SpriteBundle, ColorMaterial, Sprite, SpriteResizeMode},
+    tasks::{AsyncComputeTaskPool, Task},
+    transform::{
+        components::Transform,
+        hierarchy::{BuildChildren, DespawnRecursiveExt},
+    },
+    utils::HashSet,
 };
-use lyon_tessellation::{
-    self as tess, path::Path, BuffersBuilder, FillTessellator, FillVertex, FillVertexConstructor,
-    StrokeTessellator, StrokeVertex, StrokeVertexConstructor,
-};
+use futures_lite::future;
+use lyon_tessellation::{path::Path, FillTessellator, StrokeTessellator, TessellationError};
 
 /// Stages for this plugin.
 pub mod stage {
     /// The stage where the [`ShapeBundle`](crate::entity::ShapeBundle) gets
     /// completed.
     pub const SHAPE: &str = "shape";
+    /// The stage where the [`Batched`](crate::batch::Batched) shapes are
+    /// merged into their batch, after the transforms have been propagated.
+    pub const BATCH: &str = "shape_batch";
+}
+
+/// A plugin that provides resources and a system to draw shapes in Bevy with
+/// less boilerplate.
+///
+/// # Panics
+///
+/// The plugin registers the shape pipeline in the resources of the
+/// `RenderPlugin`, so it must be added after it, usually by adding it after
+/// the `DefaultPlugins`.
+pub struct ShapePlugin;
+
+impl Plugin for ShapePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_shape_pipeline(app.resources());
+        if !app.resources().contains::<TessellationSettings>() {
+            app.add_resource(TessellationSettings::default());
+        }
+        app.add_resource(FillTessellator::new())
+            .add_resource(StrokeTessellator::new())
+            .add_resource(MeshCache::default())
+            .add_resource(ShapeDebugDraw::default())
+            .add_event::<ShapeTessellationError>();
+        add_shape_systems(app);
+        add_batch_systems(app);
+
+        #[cfg(feature = "collider")]
+        app.add_system_to_stage(stage::SHAPE, update_shape_colliders.system());
+
+        #[cfg(feature = "svg")]
+        app.add_asset::<Svg>()
+            .init_asset_loader::<SvgLoader>()
+            .add_system_to_stage(bevy::app::stage::UPDATE, spawn_svg_shapes.system());
+    }
+}
+
+/// Registers the shape pipeline and its render graph node.
+fn add_shape_pipeline(resources: &Resources) {
+    const MISSING_RENDER_PLUGIN: &str = "the ShapePlugin must be added after the RenderPlugin";
+    let mut shaders = resources
+        .get_mut::<Assets<Shader>>()
+        .expect(MISSING_RENDER_PLUGIN);
+    let mut pipelines = resources
+        .get_mut::<Assets<PipelineDescriptor>>()
+        .expect(MISSING_RENDER_PLUGIN);
+    pipelines.set_untracked(SHAPE_PIPELINE_HANDLE, build_shape_pipeline(&mut shaders));
+    let mut render_graph = resources
+        .get_mut::<RenderGraph>()
+        .expect(MISSING_RENDER_PLUGIN);
+    add_shape_graph(&mut render_graph);
+}
+
+/// Adds the [`SHAPE`](stage::SHAPE) stage, that completes the shapes, and its
+/// systems.
+fn add_shape_systems(app: &mut AppBuilder) {
+    app.add_stage_after(
+        bevy::app::stage::UPDATE,
+        stage::SHAPE,
+        SystemStage::parallel(),
+    )
+    .add_system_to_stage(stage::SHAPE, evict_cached_meshes.system())
+    .add_system_to_stage(stage::SHAPE, attach_tessellated_meshes.system())
+    .add_system_to_stage(stage::SHAPE, complete_shape_bundle.system())
+    .add_system_to_stage(stage::SHAPE, update_outline_material.system())
+    .add_system_to_stage(stage::SHAPE, update_shape_bounds.system())
+    .add_system_to_stage(stage::SHAPE, draw_debug_shapes.system())
+    .add_system_to_stage(
+        bevy::app::stage::POST_UPDATE,
+        update_shape_gradients.system(),
+    )
+    .add_system_to_stage(
+        bevy::app::stage::POST_UPDATE,
+        update_outline_visibility.system(),
+    );
+}
+
+/// Adds the [`BATCH`](stage::BATCH) stage, that merges the
+/// [`Batched`](crate::batch::Batched) shapes, and its systems.
+fn add_batch_systems(app: &mut AppBuilder) {
+    app.add_stage_after(
+        bevy::app::stage::POST_UPDATE,
+        stage::BATCH,
+        SystemStage::parallel(),
+    )
+    .add_system_to_stage(
+        bevy::app::stage::POST_UPDATE,
+        update_batched_outlines.system(),
+    )
+    .add_system_to_stage(stage::BATCH, unbatch_shapes.system())
+    .add_system_to_stage(stage::BATCH, batch_shapes.system());
+}
+
+/// Offset on the z axis of the outline entity, relative to its parent shape.
+///
+/// It makes the outline of a shape drawn with
+/// [`TessellationMode::FillAndStroke`] render above the fill.
+const OUTLINE_Z_OFFSET: f32 = 0.001;
+
+/// Settings that control how the shapes are tessellated.
+///
+/// Insert this resource before adding the [`ShapePlugin`] to change the
+/// default settings.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TessellationSettings {
+    /// Whether the shapes are tessellated in the background, on the
+    /// [`AsyncComputeTaskPool`].
+    ///
+    /// Spawning many shapes at once then no longer stalls the frame, but their
+    /// meshes are attached a few frames later. New shapes stay invisible until
+    /// their mesh is ready, while shapes being re-tessellated keep showing
+    /// their previous mesh.
+    pub asynchronous: bool,
+    /// Whether shapes with the same path and tessellation mode share their
+    /// meshes, that are then tessellated only once. See [`MeshCache`].
+    ///
+    /// Shared meshes are replaced instead of being updated in place when a
+    /// shape changes, so this setting should not be changed once shapes have
+    /// been spawned.
+    pub cache_meshes: bool,
+}
+
+/// An event sent when the geometry of a shape could not be tessellated.
+///
+/// The shape is also marked with the [`FailedTessellation`] component until
+/// it is successfully tessellated.
+#[derive(Debug, Clone)]
+pub struct ShapeTessellationError {
+    /// The shape entity.
+    pub entity: Entity,
+    /// The tessellation mode of the shape.
+    pub mode: TessellationMode,
+    /// The error returned by Lyon.
+    pub error: TessellationError,
 }
 
-/// The index type of a Bevy [`Mesh`](bevy::render::mesh::Mesh).
-type IndexType = u32;
-/// Lyon's [`VertexBuffers`] generic data type defined for [`Vertex`].
-type VertexBuffers = tess::VertexBuffers<Vertex, IndexType>;
+/// A tessellation job running in the background, created when
+/// [`TessellationSettings::asynchronous`] is enabled.
+pub(crate) struct TessellationTask {
+    task: Task<Result<Tessellation, TessellationError>>,
+    /// The key under which the result is cached, if mesh caching is enabled.
+    key: Option<MeshKey>,
+}
 
-/// A vertex with all the necessary attributes to be inserted into a Bevy
-/// [`Mesh`](bevy::render::mesh::Mesh).
-#[derive(Debug, Clone, Copy, PartialEq)]
-struct Vertex {
-    position: [f32; 3],
-    normal: [f32; 3],
-    uv: [f32; 2],
+/// The components of a shape that receive its tessellated geometry.
+struct ShapeTarget<'a> {
+    entity: Entity,
+    mesh: &'a mut Handle<Mesh>,
+    visible: &'a mut Visible,
+    outline: Option<&'a Outline>,
+    outline_material: &'a OutlineMaterial,
 }
 
-/// Zero-sized type used to implement various vertex construction traits from
-/// Lyon.
-struct VertexConstructor;
+/// A bevy system. Queries all the [`ShapeBundle`](crate::entity::ShapeBundle)s
+/// whose path or tessellation mode changed, to complete them with a mesh.
+///
+/// If the entity already owns a mesh, the asset is updated in place instead of
+/// adding a new one, unless meshes are shared through the [`MeshCache`]. With
+/// asynchronous tessellation, a [`TessellationTask`] is inserted instead,
+/// replacing (and thus cancelling) any pending one.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn complete_shape_bundle(
+    commands: &mut Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut cache: ResMut<MeshCache>,
+    mut errors: ResMut<Events<ShapeTessellationError>>,
+    settings: Res<TessellationSettings>,
+    task_pool: Res<AsyncComputeTaskPool>,
+    mut fill_tess: ResMut<FillTessellator>,
+    mut stroke_tess: ResMut<StrokeTessellator>,
+    mut query: Query<
+        (
+            Entity,
+            (
+                &Path,
+                &TessellationMode,
+                &UvMapping,
+                &StrokeDash,
+                &StrokeTrim,
+            ),
+            &mut Handle<Mesh>,
+            &mut Visible,
+            &OutlineMaterial,
+            Option<&Outline>,
+            Option<&TessellationTask>,
+            Option<&FailedTessellation>,
+        ),
+        Or<(
+            Changed<TessellationMode>,
+            Changed<Path>,
+            Changed<UvMapping>,
+            Changed<StrokeDash>,
+            Changed<StrokeTrim>,
+        )>,
+    >,
+) {
+    let mut context = MeshContext {
+        meshes: &mut meshes,
+        cache: &mut cache,
+        errors: &mut errors,
+        fill_tess: &mut fill_tess,
+        stroke_tess: &mut stroke_tess,
+    };
+    for (
+        entity,
+        (path, tess_mode, uv_mapping, dash, trim),
+        mut mesh,
+        mut visible,
+        outline_material,
+        outline,
+        pending_task,
+        failed,
+    ) in query.iter_mut()
+    {
+        let geometry = ShapeGeometry {
+            path,
+            mode: tess_mode,
+            uv_mapping,
+            dash,
+            trim,
+        };
+        let lookup = CacheLookup::new(&settings, &mut context, &geometry);
+        if lookup.cached.is_none() && settings.asynchronous {
+            spawn_tessellation_task(commands, &task_pool, entity, &geometry, lookup.key);
+            continue;
+        }
 
-/// Enables the construction of a [`Vertex`] when using a `FillTessellator`.
-impl FillVertexConstructor<Vertex> for VertexConstructor {
-    fn new_vertex(&mut self, vertex: FillVertex) -> Vertex {
-        Vertex {
-            position: [vertex.position().x, vertex.position().y, 0.0],
-            normal: [0.0, 0.0, 1.0],
-            uv: [0.0, 0.0],
+        // A pending task would overwrite the up to date mesh.
+        if pending_task.is_some() {
+            commands.remove_one::<TessellationTask>(entity);
+        }
+        let target = ShapeTarget {
+            entity,
+            mesh: &mut mesh,
+            visible: &mut visible,
+            outline,
+            outline_material,
+        };
+        if complete_shape(commands, &mut context, &geometry, lookup, target) && failed.is_some() {
+            commands.remove_one::<FailedTessellation>(entity);
         }
     }
 }
 
-/// Enables the construction of a [`Vertex`] when using a `StrokeTessellator`.
-impl StrokeVertexConstructor<Vertex> for VertexConstructor {
-    fn new_vertex(&mut self, vertex: StrokeVertex) -> Vertex {
-        Vertex {
-            position: [vertex.position().x, vertex.position().y, 0.0],
-            normal: [0.0, 0.0, 1.0],
-            uv: [0.0, 0.0],
+/// The result of looking up the meshes of a shape in the [`MeshCache`].
+struct CacheLookup {
+    /// The key of the shape, if mesh caching is enabled.
+    key: Option<MeshKey>,
+    /// The meshes of an identical shape, if any.
+    cached: Option<ShapeMeshes>,
+}
+
+impl CacheLookup {
+    fn new(
+        settings: &TessellationSettings,
+        context: &mut MeshContext<'_>,
+        geometry: &ShapeGeometry<'_>,
+    ) -> Self {
+        if !settings.cache_meshes {
+            return Self {
+                key: None,
+                cached: None,
+            };
+        }
+
+        let key = MeshKey::new(geometry);
+        let cached = context.cache.get(&key, context.meshes);
+        Self {
+            key: Some(key),
+            cached,
         }
     }
 }
 
-/// A plugin that provides resources and a system to draw shapes in Bevy with
-/// less boilerplate.
-pub struct ShapePlugin;
+/// The resources used to tessellate the shapes and to store their meshes.
+struct MeshContext<'a> {
+    meshes: &'a mut Assets<Mesh>,
+    cache: &'a mut MeshCache,
+    errors: &'a mut Events<ShapeTessellationError>,
+    fill_tess: &'a mut FillTessellator,
+    stroke_tess: &'a mut StrokeTessellator,
+}
 
-impl Plugin for ShapePlugin {
-    fn build(&self, app: &mut AppBuilder) {
-        let fill_tess = FillTessellator::new();
-        let stroke_tess = StrokeTessellator::new();
-        app.add_resource(fill_tess)
-            .add_resource(stroke_tess)
-            .add_stage_after(
-                bevy::app::stage::UPDATE,
-                stage::SHAPE,
-                SystemStage::parallel(),
-            )
-            .add_system_to_stage(stage::SHAPE, complete_shape_bundle.system());
+/// Tessellates the geometry of a shape on the [`AsyncComputeTaskPool`], by
+/// inserting a [`TessellationTask`].
+fn spawn_tessellation_task(
+    commands: &mut Commands,
+    task_pool: &AsyncComputeTaskPool,
+    entity: Entity,
+    geometry: &ShapeGeometry<'_>,
+    key: Option<MeshKey>,
+) {
+    let tess_mode = *geometry.mode;
+    let path = geometry.path.clone();
+    let uv_mapping = *geometry.uv_mapping;
+    let dash = geometry.dash.clone();
+    let trim = *geometry.trim;
+    let task = task_pool.spawn(async move {
+        let geometry = ShapeGeometry {
+            path: &path,
+            mode: &tess_mode,
+            uv_mapping: &uv_mapping,
+            dash: &dash,
+            trim: &trim,
+        };
+        tessellate(
+            &mut FillTessellator::new(),
+            &mut StrokeTessellator::new(),
+            &geometry,
+        )
+    });
+    commands.insert_one(entity, TessellationTask { task, key });
+}
+
+/// Gives its meshes to a shape, from the cache or by tessellating its
+/// geometry right away.
+///
+/// Returns `false` if the tessellation failed, after reporting it.
+fn complete_shape(
+    commands: &mut Commands,
+    context: &mut MeshContext<'_>,
+    geometry: &ShapeGeometry<'_>,
+    lookup: CacheLookup,
+    target: ShapeTarget<'_>,
+) -> bool {
+    if let Some(shape_meshes) = lookup.cached {
+        set_shape_meshes(commands, target, shape_meshes);
+        return true;
+    }
+
+    match tessellate(context.fill_tess, context.stroke_tess, geometry) {
+        Ok(tessellation) => {
+            attach_tessellation(
+                commands,
+                context.meshes,
+                context.cache,
+                lookup.key,
+                target,
+                tessellation,
+            );
+            true
+        }
+        Err(error) => {
+            let (entity, mode) = (target.entity, *geometry.mode);
+            report_failure(commands, context.errors, entity, mode, error);
+            false
+        }
     }
 }
 
-/// A bevy system. Queries all the [`ShapeBundle`]s to complete them with a
-/// mesh.
-fn complete_shape_bundle(
+/// A bevy system. Attaches the meshes of the shapes whose
+/// [`TessellationTask`] is finished.
+///
+/// It must run before [`complete_shape_bundle`], otherwise removing a finished
+/// task could also remove a task inserted in the same frame.
+#[allow(clippy::type_complexity)]
+fn attach_tessellated_meshes(
+    commands: &mut Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut fill_tess: ResMut<FillTessellator>,
-    mut stroke_tess: ResMut<StrokeTessellator>,
+    mut cache: ResMut<MeshCache>,
+    mut errors: ResMut<Events<ShapeTessellationError>>,
     mut query: Query<(
+        Entity,
         &TessellationMode,
-        &Path,
+        &mut TessellationTask,
         &mut Handle<Mesh>,
         &mut Visible,
-        &mut Processed,
+        &OutlineMaterial,
+        Option<&Outline>,
+        Option<&FailedTessellation>,
     )>,
 ) {
-    for (tess_mode, path, mut mesh, mut visible, mut processed) in query.iter_mut() {
-        if processed.0 {
-            continue;
+    for (entity, tess_mode, mut task, mut mesh, mut visible, outline_material, outline, failed) in
+        query.iter_mut()
+    {
+        let result = match future::block_on(future::poll_once(&mut task.task)) {
+            Some(result) => result,
+            None => continue,
+        };
+        commands.remove_one::<TessellationTask>(entity);
+
+        match result {
+            Ok(tessellation) => {
+                let target = ShapeTarget {
+                    entity,
+                    mesh: &mut mesh,
+                    visible: &mut visible,
+                    outline,
+                    outline_material,
+                };
+                attach_tessellation(
+                    commands,
+                    &mut meshes,
+                    &mut cache,
+                    task.key.take(),
+                    target,
+                    tessellation,
+                );
+                if failed.is_some() {
+                    commands.remove_one::<FailedTessellation>(entity);
+                }
+            }
+            Err(error) => report_failure(commands, &mut errors, entity, *tess_mode, error),
         }
+    }
+}
 
-        let mut buffers = VertexBuffers::new();
+/// A bevy system. Keeps the material of the outline entities in sync with the
+/// [`OutlineMaterial`] of their parent shape.
+fn update_outline_material(
+    shapes: Query<(&OutlineMaterial, &Outline), Changed<OutlineMaterial>>,
+    mut outlines: Query<&mut Handle<ColorMaterial>>,
+) {
+    for (material, outline) in shapes.iter() {
+        if let Ok(mut handle) = outlines.get_mut(outline.entity) {
+            *handle = material.0.clone();
+        }
+    }
+}
 
-        match tess_mode {
-            TessellationMode::Fill(ref options) => {
-                if let Err(e) = fill_tess.tessellate_path(
-                    path,
-                    options,
-                    &mut BuffersBuilder::new(&mut buffers, VertexConstructor),
-                ) {
-                    error!("FillTessellator error: {:?}", e);
-                }
+/// A bevy system. Copies the gradients of the [`ShapePaint`] of the shapes
+/// into the [`ShapeGradient`] components drawing their fill and stroke
+/// geometries, when the paint or the tessellation mode change, or when an
+/// outline is created.
+#[allow(clippy::type_complexity)]
+fn update_shape_gradients(
+    mut shapes: Query<
+        (
+            &ShapePaint,
+            &TessellationMode,
+            &mut ShapeGradient,
+            Option<&Outline>,
+        ),
+        Or<(
+            Changed<ShapePaint>,
+            Changed<TessellationMode>,
+            Changed<Outline>,
+        )>,
+    >,
+    mut outlines: Query<&mut ShapeGradient, Without<ShapePaint>>,
+) {
+    for (paint, tess_mode, mut gradient, outline) in shapes.iter_mut() {
+        let geometry_gradient = match tess_mode {
+            TessellationMode::Fill(_) | TessellationMode::FillAndStroke(..) => &paint.fill,
+            TessellationMode::Stroke(_) => &paint.stroke,
+        };
+        *gradient = ShapeGradient::new(geometry_gradient.as_ref());
+        if let Some(outline) = outline {
+            if let Ok(mut outline_gradient) = outlines.get_mut(outline.entity) {
+                *outline_gradient = ShapeGradient::new(paint.stroke.as_ref());
             }
-            TessellationMode::Stroke(ref options) => {
-                if let Err(e) = stroke_tess.tessellate_path(
-                    path,
-                    options,
-                    &mut BuffersBuilder::new(&mut buffers, VertexConstructor),
-                ) {
-                    error!("StrokeTessellator error: {:?}", e);
-                }
+        }
+    }
+}
+
+/// A bevy system. Shows or hides the outline entities along with their parent
+/// shape.
+#[allow(clippy::type_complexity)]
+fn update_outline_visibility(
+    shapes: Query<(&Visible, &Outline), Or<(Changed<Visible>, Changed<Outline>)>>,
+    mut outlines: Query<&mut Visible, Without<Outline>>,
+) {
+    for (visible, outline) in shapes.iter() {
+        if let Ok(mut outline_visible) = outlines.get_mut(outline.entity) {
+            if outline_visible.is_visible != visible.is_visible {
+                outline_visible.is_visible = visible.is_visible;
             }
         }
+    }
+}
+
+/// A bevy system. Updates the [`ShapeBounds`] of the shapes whose path has
+/// changed, or that have just received the component.
+#[allow(clippy::type_complexity)]
+fn update_shape_bounds(
+    mut query: Query<(&Path, &mut ShapeBounds), Or<(Changed<Path>, Added<ShapeBounds>)>>,
+) {
+    for (path, mut bounds) in query.iter_mut() {
+        *bounds = path_bounds(path).unwrap_or_default();
+    }
+}
+
+/// A bevy system. Regenerates the collider data of the shapes whose path or
+/// collider options have changed.
+#[cfg(feature = "collider")]
+#[allow(clippy::type_complexity)]
+fn update_shape_colliders(
+    mut query: Query<
+        (Entity, &Path, &mut ShapeCollider),
+        Or<(Changed<Path>, Changed<ShapeCollider>)>,
+    >,
+) {
+    for (entity, path, mut collider) in query.iter_mut() {
+        if let Err(error) = collider.update(path) {
+            error!(
+                "Failed to generate the collider of {:?}: {:?}",
+                entity, error
+            );
+        }
+    }
+}
 
-        *mesh = meshes.add(build_mesh(&buffers));
+/// A bevy system. Removes from the [`MeshCache`] the meshes that have been
+/// freed because no shape uses them any more.
+fn evict_cached_meshes(
+    mut cache: ResMut<MeshCache>,
+    mut event_reader: Local<EventReader<AssetEvent<Mesh>>>,
+    events: Res<Events<AssetEvent<Mesh>>>,
+) {
+    let removed = event_reader
+        .iter(&events)
+        .filter_map(|event| match event {
+            AssetEvent::Removed { handle } => Some(handle.id),
+            _ => None,
+        })
+        .collect::<HashSet<HandleId>>();
+
+    if !removed.is_empty() {
+        cache.evict(&removed);
+    }
+}
+
+/// Marks a shape whose geometry could not be tessellated, and notifies the
+/// user with a [`ShapeTessellationError`] event.
+///
+/// The shape keeps its previous mesh, if any.
+fn report_failure(
+    commands: &mut Commands,
+    errors: &mut Events<ShapeTessellationError>,
+    entity: Entity,
+    mode: TessellationMode,
+    error: TessellationError,
+) {
+    error!("Tessellation of shape {:?} failed: {:?}", entity, error);
+    commands.insert_one(entity, FailedTessellation);
+    errors.send(ShapeTessellationError {
+        entity,
+        mode,
+        error,
+    });
+}
+
+/// Gives the tessellated geometry to a shape, sharing it through the cache
+/// when a `key` is given.
+fn attach_tessellation(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    cache: &mut MeshCache,
+    key: Option<MeshKey>,
+    target: ShapeTarget<'_>,
+    tessellation: Tessellation,
+) {
+    let key = match key {
+        Some(key) => key,
+        None => {
+            set_mesh(meshes, target.mesh, target.visible, &tessellation.buffers);
+            update_outline(commands, meshes, &target, tessellation.outline_buffers);
+            return;
+        }
+    };
+
+    // An identical shape may have been completed while this one was being
+    // tessellated in the background.
+    let shape_meshes = match cache.lookup(&key, meshes) {
+        Some(shape_meshes) => shape_meshes,
+        None => {
+            let shape_meshes = ShapeMeshes {
+                mesh: meshes.add(build_mesh(&tessellation.buffers)),
+                outline_mesh: tessellation
+                    .outline_buffers
+                    .map(|buffers| meshes.add(build_mesh(&buffers))),
+            };
+            cache.insert(key, &shape_meshes);
+            shape_meshes
+        }
+    };
+    set_shape_meshes(commands, target, shape_meshes);
+}
+
+/// Updates the mesh of a shape in place, or adds it and makes the shape
+/// visible if it doesn't have one yet.
+fn set_mesh(
+    meshes: &mut Assets<Mesh>,
+    mesh: &mut Handle<Mesh>,
+    visible: &mut Visible,
+    buffers: &VertexBuffers,
+) {
+    if let Some(existing) = meshes.get_mut(&*mesh) {
+        *existing = build_mesh(buffers);
+    } else {
+        *mesh = meshes.add(build_mesh(buffers));
         visible.is_visible = true;
-        *processed = Processed(true);
     }
 }
 
-fn build_mesh(buffers: &VertexBuffers) -> Mesh {
-    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-    mesh.set_indices(Some(Indices::U32(buffers.indices.clone())));
-    mesh.set_attribute(
-        Mesh::ATTRIBUTE_POSITION,
-        buffers
-            .vertices
-            .iter()
-            .map(|v| v.position)
-            .collect::<Vec<[f32; 3]>>(),
-    );
-    mesh.set_attribute(
-        Mesh::ATTRIBUTE_NORMAL,
-        buffers
-            .vertices
-            .iter()
-            .map(|v| v.normal)
-            .collect::<Vec<[f32; 3]>>(),
-    );
-    mesh.set_attribute(
-        Mesh::ATTRIBUTE_UV_0,
-        buffers
-            .vertices
-            .iter()
-            .map(|v| v.uv)
-            .collect::<Vec<[f32; 2]>>(),
-    );
+/// Makes a shape use the given (possibly shared) meshes.
+fn set_shape_meshes(commands: &mut Commands, target: ShapeTarget<'_>, shape_meshes: ShapeMeshes) {
+    *target.mesh = shape_meshes.mesh;
+    target.visible.is_visible = true;
+    set_outline_mesh(commands, &target, shape_meshes.outline_mesh);
+}
+
+/// Updates the mesh of the outline of a shape in place, or creates or removes
+/// the outline depending on the presence of the stroke buffers.
+fn update_outline(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    target: &ShapeTarget<'_>,
+    buffers: Option<VertexBuffers>,
+) {
+    if let (Some(outline), Some(buffers)) = (target.outline, &buffers) {
+        if let Some(existing) = meshes.get_mut(&outline.mesh) {
+            *existing = build_mesh(buffers);
+            return;
+        }
+    }
 
-    mesh
+    let mesh = buffers.map(|buffers| meshes.add(build_mesh(&buffers)));
+    set_outline_mesh(commands, target, mesh);
+}
+
+/// Creates, updates or removes the child entity that draws the outline of a
+/// shape, depending on the presence of its mesh.
+fn set_outline_mesh(commands: &mut Commands, target: &ShapeTarget<'_>, mesh: Option<Handle<Mesh>>) {
+    let entity = target.entity;
+    match (target.outline, mesh) {
+        (Some(outline), Some(mesh)) => {
+            if outline.mesh != mesh {
+                commands
+                    .insert_one(outline.entity, mesh.clone())
+                    .insert_one(
+                        entity,
+                        Outline {
+                            entity: outline.entity,
+                            mesh,
+                        },
+                    );
+            }
+        }
+        (None, Some(mesh)) => {
+            let child = commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        size: Vec2::new(1.0, 1.0),
+                        resize_mode: SpriteResizeMode::Manual,
+                    },
+                    mesh: mesh.clone(),
+                    material: target.outline_material.0.clone(),
+                    render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
+                        SHAPE_PIPELINE_HANDLE.typed(),
+                    )]),
+                    transform: Transform::from_translation(Vec3::new(0.0, 0.0, OUTLINE_Z_OFFSET)),
+                    ..SpriteBundle::default()
+                })
+                .with(ShapeGradient::default())
+                .current_entity()
+                .expect("the outline entity has just been spawned");
+            commands.push_children(entity, &[child]).insert_one(
+                entity,
+                Outline {
+                    entity: child,
+                    mesh,
+                },
+            );
+        }
+        (Some(outline), None) => {
+            commands
+                .despawn_recursive(outline.entity)
+                .remove_one::<Outline>(entity);
+        }
+        (None, None) => {}
+    }
 }
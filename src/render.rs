@@ -0,0 +1,183 @@
+//! The render pipeline used to draw shapes.
+//!
+//! It works like the sprite pipeline, but it also multiplies the color of the
+//! material by the color of each vertex, so that a single mesh can contain
+//! differently colored geometries. See
+//! [`GeometryBuilder::add_with_color`](crate::geometry::GeometryBuilder::add_with_color).
+//!
+//! The color is also multiplied by the gradient of the [`ShapeGradient`]
+//! component of the entity, evaluated for every pixel at its position in the
+//! coordinate system of the path.
+
+use crate::paint::{Gradient, GradientKind, SpreadMethod};
+use bevy::{
+    asset::{Assets, HandleUntyped},
+    log::warn,
+    math::{Mat4, Vec4},
+    reflect::TypeUuid,
+    render::{
+        pipeline::{
+            BlendDescriptor, BlendFactor, BlendOperation, ColorStateDescriptor, ColorWrite,
+            CompareFunction, CullMode, DepthStencilStateDescriptor, FrontFace, PipelineDescriptor,
+            RasterizationStateDescriptor, StencilStateDescriptor, StencilStateFaceDescriptor,
+        },
+        render_graph::{base, RenderGraph, RenderResourcesNode},
+        renderer::RenderResources,
+        shader::{Shader, ShaderStage, ShaderStages},
+        texture::TextureFormat,
+    },
+};
+
+/// The handle of the pipeline used to draw shapes.
+pub const SHAPE_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 7_396_245_781_356_210_441);
+
+/// The name of the mesh attribute holding the linear RGBA color of the
+/// vertices.
+pub const ATTRIBUTE_COLOR: &str = "Vertex_Color";
+
+/// The name of the render graph node binding the [`ShapeGradient`]s.
+const SHAPE_GRADIENT_NODE: &str = "shape_gradient";
+
+/// The maximum number of stops of a gradient drawn by the shape pipeline.
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+/// Component holding the gradient drawn by the shape pipeline for an entity,
+/// in the layout of the uniforms of the shader.
+///
+/// The [`ShapePlugin`](crate::plugin::ShapePlugin) keeps it in sync with the
+/// [`ShapePaint`](crate::paint::ShapePaint) of the shapes: the fill gradient
+/// for the fill geometry, and the stroke gradient for the stroke geometry,
+/// which is drawn by the outline entity in
+/// [`FillAndStroke`](crate::utils::TessellationMode::FillAndStroke) mode.
+/// Every entity drawn with the shape pipeline needs this component; the
+/// default one draws no gradient.
+///
+/// Only the first [`MAX_GRADIENT_STOPS`] stops of a gradient are drawn.
+#[derive(Debug, Clone, Copy, Default, RenderResources)]
+pub struct ShapeGradient {
+    /// The kind of gradient (0 for none, 1 for linear and 2 for radial), the
+    /// spread method and the number of stops.
+    params: Vec4,
+    /// The start and end points of a linear gradient, or the center and
+    /// radius of a radial one.
+    geometry: Vec4,
+    /// The offsets of the first four stops.
+    offsets_low: Vec4,
+    /// The offsets of the last four stops.
+    offsets_high: Vec4,
+    /// The linear colors of the first four stops, as columns.
+    colors_low: Mat4,
+    /// The linear colors of the last four stops, as columns.
+    colors_high: Mat4,
+}
+
+impl ShapeGradient {
+    /// Creates the uniforms drawing the given gradient, or no gradient.
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn new(gradient: Option<&Gradient>) -> Self {
+        let gradient = match gradient {
+            Some(gradient) => gradient,
+            None => return Self::default(),
+        };
+        let (kind, geometry) = match gradient.kind {
+            GradientKind::Linear { start, end } => (1.0, Vec4::new(start.x, start.y, end.x, end.y)),
+            GradientKind::Radial { center, radius } => {
+                (2.0, Vec4::new(center.x, center.y, radius, 0.0))
+            }
+        };
+        let spread = match gradient.spread {
+            SpreadMethod::Pad => 0.0,
+            SpreadMethod::Repeat => 1.0,
+            SpreadMethod::Reflect => 2.0,
+        };
+        if gradient.stops.len() > MAX_GRADIENT_STOPS {
+            warn!(
+                "Only the first {} stops of a gradient are drawn, {} are ignored",
+                MAX_GRADIENT_STOPS,
+                gradient.stops.len() - MAX_GRADIENT_STOPS
+            );
+        }
+        let stops = &gradient.stops[..gradient.stops.len().min(MAX_GRADIENT_STOPS)];
+        let mut offsets = [0.0; MAX_GRADIENT_STOPS];
+        let mut colors = [Vec4::zero(); MAX_GRADIENT_STOPS];
+        for (i, stop) in stops.iter().enumerate() {
+            offsets[i] = stop.offset;
+            colors[i] = <[f32; 4]>::from(stop.color).into();
+        }
+
+        Self {
+            params: Vec4::new(kind, spread, stops.len() as f32, 0.0),
+            geometry,
+            offsets_low: Vec4::new(offsets[0], offsets[1], offsets[2], offsets[3]),
+            offsets_high: Vec4::new(offsets[4], offsets[5], offsets[6], offsets[7]),
+            colors_low: Mat4::from_cols(colors[0], colors[1], colors[2], colors[3]),
+            colors_high: Mat4::from_cols(colors[4], colors[5], colors[6], colors[7]),
+        }
+    }
+
+    /// Returns `true` if the component draws a gradient.
+    pub(crate) fn is_active(&self) -> bool {
+        self.params.x != 0.0
+    }
+}
+
+/// Adds the node binding the [`ShapeGradient`]s to the render graph.
+pub(crate) fn add_shape_graph(render_graph: &mut RenderGraph) {
+    render_graph.add_system_node(
+        SHAPE_GRADIENT_NODE,
+        RenderResourcesNode::<ShapeGradient>::new(true),
+    );
+    render_graph
+        .add_node_edge(SHAPE_GRADIENT_NODE, base::node::MAIN_PASS)
+        .expect("the main pass node should exist");
+}
+
+/// Builds the descriptor of the pipeline used to draw shapes.
+pub(crate) fn build_shape_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescriptor {
+    PipelineDescriptor {
+        rasterization_state: Some(RasterizationStateDescriptor {
+            front_face: FrontFace::Ccw,
+            cull_mode: CullMode::None,
+            depth_bias: 0,
+            depth_bias_slope_scale: 0.0,
+            depth_bias_clamp: 0.0,
+            clamp_depth: false,
+        }),
+        depth_stencil_state: Some(DepthStencilStateDescriptor {
+            format: TextureFormat::Depth32Float,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::LessEqual,
+            stencil: StencilStateDescriptor {
+                front: StencilStateFaceDescriptor::IGNORE,
+                back: StencilStateFaceDescriptor::IGNORE,
+                read_mask: 0,
+                write_mask: 0,
+            },
+        }),
+        color_states: vec![ColorStateDescriptor {
+            format: TextureFormat::default(),
+            color_blend: BlendDescriptor {
+                src_factor: BlendFactor::SrcAlpha,
+                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                operation: BlendOperation::Add,
+            },
+            alpha_blend: BlendDescriptor {
+                src_factor: BlendFactor::One,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+            write_mask: ColorWrite::ALL,
+        }],
+        ..PipelineDescriptor::new(ShaderStages {
+            vertex: shaders.add(Shader::from_glsl(
+                ShaderStage::Vertex,
+                include_str!("shaders/shape.vert"),
+            )),
+            fragment: Some(shaders.add(Shader::from_glsl(
+                ShaderStage::Fragment,
+                include_str!("shaders/shape.frag"),
+            ))),
+        })
+    }
+}
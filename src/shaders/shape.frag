@@ -0,0 +1,95 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+layout(location = 1) in vec4 v_Color;
+layout(location = 2) in vec2 v_Position;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 1, binding = 0) uniform ColorMaterial_color {
+    vec4 Color;
+};
+
+layout(set = 2, binding = 2) uniform ShapeGradient_params {
+    vec4 GradientParams;
+};
+layout(set = 2, binding = 3) uniform ShapeGradient_geometry {
+    vec4 GradientGeometry;
+};
+layout(set = 2, binding = 4) uniform ShapeGradient_offsets_low {
+    vec4 GradientOffsetsLow;
+};
+layout(set = 2, binding = 5) uniform ShapeGradient_offsets_high {
+    vec4 GradientOffsetsHigh;
+};
+layout(set = 2, binding = 6) uniform ShapeGradient_colors_low {
+    mat4 GradientColorsLow;
+};
+layout(set = 2, binding = 7) uniform ShapeGradient_colors_high {
+    mat4 GradientColorsHigh;
+};
+
+# ifdef COLORMATERIAL_TEXTURE
+layout(set = 1, binding = 1) uniform texture2D ColorMaterial_texture;
+layout(set = 1, binding = 2) uniform sampler ColorMaterial_texture_sampler;
+# endif
+
+float gradient_offset(vec2 position) {
+    float offset;
+    if (GradientParams.x == 1.0) {
+        vec2 start = GradientGeometry.xy;
+        vec2 direction = GradientGeometry.zw - start;
+        float length_squared = dot(direction, direction);
+        offset = length_squared > 0.0 ? dot(position - start, direction) / length_squared : 0.0;
+    } else {
+        float radius = GradientGeometry.z;
+        offset = radius > 0.0 ? distance(position, GradientGeometry.xy) / radius : 1.0;
+    }
+
+    if (GradientParams.y == 1.0) {
+        offset = fract(offset);
+    } else if (GradientParams.y == 2.0) {
+        offset = mod(offset, 2.0);
+        offset = offset > 1.0 ? 2.0 - offset : offset;
+    }
+    return offset;
+}
+
+vec4 gradient_color(vec2 position) {
+    int count = int(GradientParams.z);
+    if (count == 0) {
+        return vec4(0.0);
+    }
+
+    float offset = gradient_offset(position);
+    float from_offset = GradientOffsetsLow[0];
+    vec4 from_color = GradientColorsLow[0];
+    if (offset <= from_offset) {
+        return from_color;
+    }
+    for (int i = 1; i < count; i++) {
+        float to_offset = i < 4 ? GradientOffsetsLow[i] : GradientOffsetsHigh[i - 4];
+        vec4 to_color = i < 4 ? GradientColorsLow[i] : GradientColorsHigh[i - 4];
+        if (offset <= to_offset) {
+            float range = to_offset - from_offset;
+            float t = range > 0.0 ? (offset - from_offset) / range : 1.0;
+            return mix(from_color, to_color, t);
+        }
+        from_offset = to_offset;
+        from_color = to_color;
+    }
+    return from_color;
+}
+
+void main() {
+    vec4 color = Color * v_Color;
+    if (GradientParams.x != 0.0) {
+        color *= gradient_color(v_Position);
+    }
+# ifdef COLORMATERIAL_TEXTURE
+    color *= texture(
+        sampler2D(ColorMaterial_texture, ColorMaterial_texture_sampler),
+        v_Uv);
+# endif
+    o_Target = color;
+}
@@ -4,14 +4,16 @@
 //! [`Geometry`](crate::geometry::Geometry) trait. You can also implement
 //! the trait for your own shapes.
 
-use crate::{geometry::Geometry, utils::Convert};
+use crate::{geometry::Geometry, measure::MeasuredPath, utils::Convert};
 use bevy::math::Vec2;
 use lyon_tessellation::{
-    math::{point, Angle, Point, Rect, Size},
-    path::{path::Builder, traits::PathBuilder, Polygon as LyonPolygon, Winding},
+    geom::Arc as LyonArc,
+    math::{point, vector, Angle, Point, Rect, Size, Vector},
+    path::{path::Builder, traits::PathBuilder, Path, Polygon as LyonPolygon, Winding},
 };
 
-/// Defines where the origin, or pivot of the `Rectangle` should be positioned.
+/// Defines where the origin, or pivot of the `Rectangle` or of the
+/// `RoundedRectangle` should be positioned.
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RectangleOrigin {
@@ -47,18 +49,24 @@ impl Default for Rectangle {
     }
 }
 
+impl RectangleOrigin {
+    /// Returns the bottom left corner of a rectangle of the given size placed
+    /// at this origin.
+    fn min_corner(self, width: f32, height: f32) -> Point {
+        match self {
+            Self::Center => Point::new(-width / 2.0, -height / 2.0),
+            Self::BottomLeft => Point::new(0.0, 0.0),
+            Self::BottomRight => Point::new(-width, 0.0),
+            Self::TopRight => Point::new(-width, -height),
+            Self::TopLeft => Point::new(0.0, -height),
+            Self::CustomCenter(v) => Point::new(v.x - width / 2.0, v.y - height / 2.0),
+        }
+    }
+}
+
 impl Geometry for Rectangle {
     fn add_geometry(&self, b: &mut Builder) {
-        let origin = match self.origin {
-            RectangleOrigin::Center => Point::new(-self.width / 2.0, -self.height / 2.0),
-            RectangleOrigin::BottomLeft => Point::new(0.0, 0.0),
-            RectangleOrigin::BottomRight => Point::new(-self.width, 0.0),
-            RectangleOrigin::TopRight => Point::new(-self.width, -self.height),
-            RectangleOrigin::TopLeft => Point::new(0.0, -self.height),
-            RectangleOrigin::CustomCenter(v) => {
-                Point::new(v.x - self.width / 2.0, v.y - self.height / 2.0)
-            }
-        };
+        let origin = self.origin.min_corner(self.width, self.height);
 
         b.add_rectangle(
             &Rect::new(origin, Size::new(self.width, self.height)),
@@ -67,6 +75,135 @@ impl Geometry for Rectangle {
     }
 }
 
+/// The radii of the corners of a [`RoundedRectangle`].
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CornerRadii {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+impl CornerRadii {
+    /// Returns the same radius for all the corners.
+    #[must_use]
+    pub const fn uniform(radius: f32) -> Self {
+        Self {
+            top_left: radius,
+            top_right: radius,
+            bottom_right: radius,
+            bottom_left: radius,
+        }
+    }
+
+    /// Returns the absolute values of the radii.
+    fn abs(self) -> Self {
+        Self {
+            top_left: self.top_left.abs(),
+            top_right: self.top_right.abs(),
+            bottom_right: self.bottom_right.abs(),
+            bottom_left: self.bottom_left.abs(),
+        }
+    }
+
+    /// Returns the factor, at most 1, by which the radii are scaled so that
+    /// the corners of a side of the given rectangle don't overlap.
+    fn overlap_scale(self, width: f32, height: f32) -> f32 {
+        [
+            width / (self.top_left + self.top_right),
+            width / (self.bottom_left + self.bottom_right),
+            height / (self.top_left + self.bottom_left),
+            height / (self.top_right + self.bottom_right),
+        ]
+        .iter()
+        .fold(1.0_f32, |scale, ratio| scale.min(*ratio))
+    }
+}
+
+/// A rectangle with rounded corners.
+///
+/// Like in CSS, the radii are scaled down uniformly if the corners of a side
+/// would overlap.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundedRectangle {
+    pub width: f32,
+    pub height: f32,
+    pub radii: CornerRadii,
+    pub origin: RectangleOrigin,
+}
+
+impl Default for RoundedRectangle {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            height: 1.0,
+            radii: CornerRadii::uniform(0.25),
+            origin: RectangleOrigin::default(),
+        }
+    }
+}
+
+/// The distance from its endpoints to the control points of a cubic bezier
+/// approximating a quarter of a circle of radius 1.
+///
+/// See <https://spencermortensen.com/articles/bezier-circle/>.
+const QUARTER_CIRCLE_CONTROL: f32 = 0.551_915_05;
+
+impl Geometry for RoundedRectangle {
+    fn add_geometry(&self, b: &mut Builder) {
+        let (width, height) = (self.width.abs(), self.height.abs());
+        let min = self.origin.min_corner(width, height);
+        let max = min + vector(width, height);
+        let radii = self.radii.abs();
+        let scale = radii.overlap_scale(width, height);
+        let CornerRadii {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        } = radii;
+
+        // Each corner is given with the directions from the corner to the
+        // start and to the end of its arc, going counter-clockwise.
+        let corners = [
+            (min, bottom_left, vector(0.0, 1.0), vector(1.0, 0.0)),
+            (
+                point(max.x, min.y),
+                bottom_right,
+                vector(-1.0, 0.0),
+                vector(0.0, 1.0),
+            ),
+            (max, top_right, vector(0.0, -1.0), vector(-1.0, 0.0)),
+            (
+                point(min.x, max.y),
+                top_left,
+                vector(1.0, 0.0),
+                vector(0.0, -1.0),
+            ),
+        ];
+        for (i, &(corner, radius, from, to)) in corners.iter().enumerate() {
+            let radius = radius * scale;
+            let start = corner + from * radius;
+            if i == 0 {
+                b.begin(start);
+            } else {
+                b.line_to(start);
+            }
+            if radius > 0.0 {
+                let control = radius * (1.0 - QUARTER_CIRCLE_CONTROL);
+                b.cubic_bezier_to(
+                    corner + from * control,
+                    corner + to * control,
+                    corner + to * radius,
+                );
+            }
+        }
+        b.end(true);
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Circle {
@@ -160,15 +297,49 @@ pub enum RegularPolygonFeature {
     SideLength(f32),
 }
 
+/// A regular polygon.
+///
+/// With a `rotation` of zero, the bottom side is parallel to the x axis.
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RegularPolygon {
     pub sides: usize,
     pub center: Vec2,
     pub feature: RegularPolygonFeature,
+    /// The counter-clockwise rotation of the polygon, in radians.
+    pub rotation: f32,
 }
 
 impl RegularPolygon {
+    /// Creates a hexagon centered on the origin, with the given orientation.
+    ///
+    /// ```
+    /// use bevy::math::Vec2;
+    /// use bevy_prototype_lyon::shapes::{HexOrientation, RegularPolygon};
+    ///
+    /// // The center of the hexagon in the given column and row of a map
+    /// // where odd columns are shifted up by half a hexagon.
+    /// fn hex_center(column: i32, row: i32, radius: f32) -> Vec2 {
+    ///     let spacing = HexOrientation::FlatTop.spacing(radius);
+    ///     let shift = if column % 2 == 0 { 0.0 } else { 0.5 };
+    ///     Vec2::new(column as f32 * spacing.x, (row as f32 + shift) * spacing.y)
+    /// }
+    ///
+    /// let tile = RegularPolygon {
+    ///     center: hex_center(3, 2, 32.0),
+    ///     ..RegularPolygon::hexagon(32.0, HexOrientation::FlatTop)
+    /// };
+    /// ```
+    #[must_use]
+    pub fn hexagon(radius: f32, orientation: HexOrientation) -> Self {
+        Self {
+            sides: 6,
+            center: Vec2::zero(),
+            feature: RegularPolygonFeature::Radius(radius),
+            rotation: orientation.rotation(),
+        }
+    }
+
     /// Gets the radius of the polygon.
     fn radius(&self) -> f32 {
         let ratio = std::f32::consts::PI / self.sides as f32;
@@ -187,6 +358,7 @@ impl Default for RegularPolygon {
             sides: 3,
             center: Vec2::zero(),
             feature: RegularPolygonFeature::Radius(1.0),
+            rotation: 0.0,
         }
     }
 }
@@ -196,14 +368,15 @@ impl Geometry for RegularPolygon {
         // -- Implementation details **PLEASE KEEP UPDATED** --
         // - `step`: angle between two vertices.
         // - `internal`: internal angle of the polygon.
-        // - `offset`: bias to make the shape lay flat on a line parallel to the x-axis.
+        // - `offset`: bias to make the shape lay flat on a line parallel to the x-axis,
+        //   plus the rotation.
 
         use std::f32::consts::PI;
         assert!(self.sides > 2, "Polygons must have at least 3 sides");
         let n = self.sides as f32;
         let radius = self.radius();
         let internal = (n - 2.0) * PI / n;
-        let offset = -internal / 2.0;
+        let offset = self.rotation - internal / 2.0;
 
         let mut points = Vec::with_capacity(self.sides);
         let step = 2.0 * PI / n;
@@ -223,6 +396,45 @@ impl Geometry for RegularPolygon {
     }
 }
 
+/// The orientation of a hexagon, following the usual conventions of hex grids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexOrientation {
+    /// The top and bottom sides are horizontal. Columns of hexagons are
+    /// straight, and rows zigzag.
+    FlatTop,
+    /// A vertex points straight up. Rows of hexagons are straight, and columns
+    /// zigzag.
+    PointyTop,
+}
+
+impl HexOrientation {
+    /// Returns the [`RegularPolygon::rotation`] of a hexagon with this
+    /// orientation.
+    #[must_use]
+    pub const fn rotation(self) -> f32 {
+        match self {
+            Self::FlatTop => 0.0,
+            Self::PointyTop => std::f32::consts::FRAC_PI_6,
+        }
+    }
+
+    /// Returns the horizontal distance between adjacent columns and the
+    /// vertical distance between adjacent rows of a grid of hexagons of the
+    /// given circumradius.
+    ///
+    /// Every other column (for [`FlatTop`](Self::FlatTop)) or row (for
+    /// [`PointyTop`](Self::PointyTop)) is shifted by half the spacing in the
+    /// other direction.
+    #[must_use]
+    pub fn spacing(self, radius: f32) -> Vec2 {
+        let width = 3.0_f32.sqrt() * radius;
+        match self {
+            Self::FlatTop => Vec2::new(1.5 * radius, width),
+            Self::PointyTop => Vec2::new(width, 1.5 * radius),
+        }
+    }
+}
+
 /// A simple line segment, specified by two points.
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -236,3 +448,541 @@ impl Geometry for Line {
         });
     }
 }
+
+/// A star, with points alternating between an outer and an inner circle.
+///
+/// With a `rotation` of zero, the first point is straight up.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Star {
+    pub points: usize,
+    pub outer_radius: f32,
+    pub inner_radius: f32,
+    pub center: Vec2,
+    /// The counter-clockwise rotation of the star, in radians.
+    pub rotation: f32,
+}
+
+impl Default for Star {
+    fn default() -> Self {
+        Self {
+            points: 5,
+            outer_radius: 1.0,
+            inner_radius: 0.5,
+            center: Vec2::zero(),
+            rotation: 0.0,
+        }
+    }
+}
+
+impl Geometry for Star {
+    fn add_geometry(&self, b: &mut Builder) {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        assert!(self.points > 1, "Stars must have at least 2 points");
+        let step = PI / self.points as f32;
+        let offset = FRAC_PI_2 + self.rotation;
+
+        let points = (0..self.points * 2)
+            .map(|i| {
+                let radius = if i % 2 == 0 {
+                    self.outer_radius
+                } else {
+                    self.inner_radius
+                };
+                point_on_circle(self.center, radius, (i as f32).mul_add(step, offset))
+            })
+            .collect::<Vec<Point>>();
+
+        b.add_polygon(LyonPolygon {
+            points: points.as_slice(),
+            closed: true,
+        });
+    }
+}
+
+/// Adds to the current sub-path of `b` an arc of a circle, that must start at
+/// its current position.
+///
+/// The angles are in radians, counter-clockwise from the x axis.
+fn add_arc(b: &mut Builder, center: Point, radius: f32, start_angle: f32, sweep_angle: f32) {
+    LyonArc {
+        center,
+        radii: vector(radius, radius),
+        start_angle: Angle::radians(start_angle),
+        sweep_angle: Angle::radians(sweep_angle),
+        x_rotation: Angle::zero(),
+    }
+    .for_each_quadratic_bezier(&mut |curve| {
+        b.quadratic_bezier_to(curve.ctrl, curve.to);
+    });
+}
+
+/// Returns the point of a circle at the given angle.
+fn point_on_circle(center: Vec2, radius: f32, angle: f32) -> Point {
+    point(
+        radius.mul_add(angle.cos(), center.x),
+        radius.mul_add(angle.sin(), center.y),
+    )
+}
+
+/// An open arc of a circle.
+///
+/// The angles are in radians, counter-clockwise from the x axis. A negative
+/// `sweep_angle` goes clockwise.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arc {
+    pub center: Vec2,
+    pub radius: f32,
+    pub start_angle: f32,
+    pub sweep_angle: f32,
+}
+
+impl Default for Arc {
+    fn default() -> Self {
+        Self {
+            center: Vec2::zero(),
+            radius: 1.0,
+            start_angle: 0.0,
+            sweep_angle: std::f32::consts::FRAC_PI_2,
+        }
+    }
+}
+
+impl Geometry for Arc {
+    fn add_geometry(&self, b: &mut Builder) {
+        b.begin(point_on_circle(self.center, self.radius, self.start_angle));
+        add_arc(
+            b,
+            self.center.convert(),
+            self.radius,
+            self.start_angle,
+            self.sweep_angle,
+        );
+        b.end(false);
+    }
+}
+
+/// A pie slice: the part of a disk between two radii.
+///
+/// The angles are in radians, counter-clockwise from the x axis. A negative
+/// `sweep_angle` goes clockwise.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sector {
+    pub center: Vec2,
+    pub radius: f32,
+    pub start_angle: f32,
+    pub sweep_angle: f32,
+}
+
+impl Default for Sector {
+    fn default() -> Self {
+        Self {
+            center: Vec2::zero(),
+            radius: 1.0,
+            start_angle: 0.0,
+            sweep_angle: std::f32::consts::FRAC_PI_2,
+        }
+    }
+}
+
+impl Geometry for Sector {
+    fn add_geometry(&self, b: &mut Builder) {
+        b.begin(self.center.convert());
+        b.line_to(point_on_circle(self.center, self.radius, self.start_angle));
+        add_arc(
+            b,
+            self.center.convert(),
+            self.radius,
+            self.start_angle,
+            self.sweep_angle,
+        );
+        b.end(true);
+    }
+}
+
+/// A ring: the area between two concentric circles.
+///
+/// The inner circle is wound in the opposite direction, so that it is a hole
+/// with both fill rules.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Annulus {
+    pub center: Vec2,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+}
+
+impl Default for Annulus {
+    fn default() -> Self {
+        Self {
+            center: Vec2::zero(),
+            inner_radius: 0.5,
+            outer_radius: 1.0,
+        }
+    }
+}
+
+impl Geometry for Annulus {
+    fn add_geometry(&self, b: &mut Builder) {
+        b.add_circle(self.center.convert(), self.outer_radius, Winding::Positive);
+        b.add_circle(self.center.convert(), self.inner_radius, Winding::Negative);
+    }
+}
+
+/// A part of a ring, between two angles.
+///
+/// The angles are in radians, counter-clockwise from the x axis. A negative
+/// `sweep_angle` goes clockwise.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RingSector {
+    pub center: Vec2,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub start_angle: f32,
+    pub sweep_angle: f32,
+}
+
+impl Default for RingSector {
+    fn default() -> Self {
+        Self {
+            center: Vec2::zero(),
+            inner_radius: 0.5,
+            outer_radius: 1.0,
+            start_angle: 0.0,
+            sweep_angle: std::f32::consts::FRAC_PI_2,
+        }
+    }
+}
+
+impl Geometry for RingSector {
+    fn add_geometry(&self, b: &mut Builder) {
+        let center = self.center.convert();
+        let end_angle = self.start_angle + self.sweep_angle;
+
+        b.begin(point_on_circle(
+            self.center,
+            self.outer_radius,
+            self.start_angle,
+        ));
+        add_arc(
+            b,
+            center,
+            self.outer_radius,
+            self.start_angle,
+            self.sweep_angle,
+        );
+        b.line_to(point_on_circle(self.center, self.inner_radius, end_angle));
+        add_arc(b, center, self.inner_radius, end_angle, -self.sweep_angle);
+        b.end(true);
+    }
+}
+
+/// A smooth curve going through all the points.
+///
+/// The curve is a cardinal spline: a `tension` of 0 gives a Catmull-Rom
+/// spline, and a `tension` of 1 gives straight lines.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatmullRom {
+    pub points: Vec<Vec2>,
+    pub tension: f32,
+    pub closed: bool,
+}
+
+impl Default for CatmullRom {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            tension: 0.0,
+            closed: true,
+        }
+    }
+}
+
+impl Geometry for CatmullRom {
+    fn add_geometry(&self, b: &mut Builder) {
+        let n = self.points.len();
+        if n < 2 {
+            return;
+        }
+        let get = |i: usize| self.points[i % n];
+        let segments = if self.closed { n } else { n - 1 };
+        // The tangent at a point is `(1 - tension) * (next - previous) / 2`,
+        // and the control points are a third of it away from the point.
+        let scale = (1.0 - self.tension) / 6.0;
+
+        b.begin(self.points[0].convert());
+        for i in 0..segments {
+            let (p1, p2) = (get(i), get(i + 1));
+            let p0 = if i == 0 && !self.closed {
+                p1
+            } else {
+                get(i + n - 1)
+            };
+            let p3 = if i + 2 >= n && !self.closed {
+                p2
+            } else {
+                get(i + 2)
+            };
+
+            b.cubic_bezier_to(
+                (p1 + (p2 - p0) * scale).convert(),
+                (p2 - (p3 - p1) * scale).convert(),
+                p2.convert(),
+            );
+        }
+        b.end(self.closed);
+    }
+}
+
+/// A smooth curve approximating the points, which act like the control
+/// points of a uniform cubic B-spline.
+///
+/// An open B-spline starts on the first point and ends on the last one.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BSpline {
+    pub points: Vec<Vec2>,
+    pub closed: bool,
+}
+
+impl Default for BSpline {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            closed: true,
+        }
+    }
+}
+
+impl Geometry for BSpline {
+    fn add_geometry(&self, b: &mut Builder) {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) if self.points.len() > 1 => (*first, *last),
+            _ => return,
+        };
+        // The ends of an open spline are repeated, so that the curve reaches
+        // them.
+        let points = if self.closed {
+            self.points.clone()
+        } else {
+            std::iter::repeat(first)
+                .take(2)
+                .chain(self.points.iter().copied())
+                .chain(std::iter::repeat(last).take(2))
+                .collect()
+        };
+        let n = points.len();
+        let get = |i: usize| points[i % n];
+        let segments = if self.closed { n } else { n - 3 };
+
+        b.begin(((get(0) + get(1) * 4.0 + get(2)) / 6.0).convert());
+        for i in 0..segments {
+            let (p1, p2, p3) = (get(i + 1), get(i + 2), get(i + 3));
+            b.cubic_bezier_to(
+                ((p1 * 2.0 + p2) / 3.0).convert(),
+                ((p1 + p2 * 2.0) / 3.0).convert(),
+                ((p1 + p2 * 4.0 + p3) / 6.0).convert(),
+            );
+        }
+        b.end(self.closed);
+    }
+}
+
+/// The marker drawn at an end of an [`Arrow`].
+///
+/// The markers point away from the shaft and end exactly at the end of the
+/// arrow. Closed markers are meant to be filled, for example with
+/// [`TessellationMode::FillAndStroke`](crate::utils::TessellationMode::FillAndStroke).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrowHead {
+    /// No marker.
+    None,
+    /// A closed triangle.
+    Triangle {
+        /// The length of the triangle, along the shaft.
+        length: f32,
+        /// The width of the base of the triangle.
+        width: f32,
+    },
+    /// Two lines forming an open V.
+    OpenV {
+        /// The length of the lines, along the shaft.
+        length: f32,
+        /// The distance between the ends of the lines.
+        width: f32,
+    },
+    /// A circle.
+    Circle {
+        /// The radius of the circle.
+        radius: f32,
+    },
+    /// A closed diamond.
+    Diamond {
+        /// The length of the diamond, along the shaft.
+        length: f32,
+        /// The width of the diamond.
+        width: f32,
+    },
+}
+
+impl ArrowHead {
+    /// Returns the length of the marker along the shaft.
+    fn length(self) -> f32 {
+        match self {
+            Self::None => 0.0,
+            Self::Triangle { length, .. }
+            | Self::OpenV { length, .. }
+            | Self::Diamond { length, .. } => length,
+            Self::Circle { radius } => radius * 2.0,
+        }
+    }
+
+    /// Returns the length by which the shaft is shortened, so that it doesn't
+    /// cross the marker.
+    fn shaft_trim(self) -> f32 {
+        match self {
+            Self::None | Self::OpenV { .. } => 0.0,
+            _ => self.length(),
+        }
+    }
+
+    /// Adds the marker to `b`, with its tip at `tip`, pointing in the
+    /// `direction` unit vector.
+    fn add_geometry(self, b: &mut Builder, tip: Point, direction: Vector) {
+        let normal = vector(-direction.y, direction.x);
+        match self {
+            Self::None => {}
+            Self::Triangle { length, width } => {
+                let base = tip - direction * length;
+                b.add_polygon(LyonPolygon {
+                    points: &[
+                        tip,
+                        base + normal * width / 2.0,
+                        base - normal * width / 2.0,
+                    ],
+                    closed: true,
+                });
+            }
+            Self::OpenV { length, width } => {
+                let base = tip - direction * length;
+                b.add_polygon(LyonPolygon {
+                    points: &[
+                        base + normal * width / 2.0,
+                        tip,
+                        base - normal * width / 2.0,
+                    ],
+                    closed: false,
+                });
+            }
+            Self::Circle { radius } => {
+                b.add_circle(tip - direction * radius, radius.abs(), Winding::Positive);
+            }
+            Self::Diamond { length, width } => {
+                let middle = tip - direction * length / 2.0;
+                b.add_polygon(LyonPolygon {
+                    points: &[
+                        tip,
+                        middle + normal * width / 2.0,
+                        tip - direction * length,
+                        middle - normal * width / 2.0,
+                    ],
+                    closed: true,
+                });
+            }
+        }
+    }
+}
+
+/// The shape of the shaft of an [`Arrow`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrowShaft {
+    /// A straight line.
+    Straight,
+    /// A quadratic bezier curve, with the given control point.
+    Quadratic(Vec2),
+    /// A cubic bezier curve, with the given control points.
+    Cubic(Vec2, Vec2),
+}
+
+/// An arrow or connector going from `start` to `end`, with optional markers at
+/// both ends.
+///
+/// The shaft is shortened under the closed markers, so that its stroke doesn't
+/// show through them, and the markers follow the direction of the shaft.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arrow {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub shaft: ArrowShaft,
+    pub start_head: ArrowHead,
+    pub end_head: ArrowHead,
+}
+
+impl Default for Arrow {
+    fn default() -> Self {
+        Self {
+            start: Vec2::zero(),
+            end: Vec2::new(1.0, 0.0),
+            shaft: ArrowShaft::Straight,
+            start_head: ArrowHead::None,
+            end_head: ArrowHead::Triangle {
+                length: 0.2,
+                width: 0.2,
+            },
+        }
+    }
+}
+
+/// The tolerance used to measure the shaft of an [`Arrow`].
+const ARROW_TOLERANCE: f32 = 0.01;
+
+impl Geometry for Arrow {
+    fn add_geometry(&self, b: &mut Builder) {
+        let (start, end) = (self.start.convert(), self.end.convert());
+        let mut shaft = Path::builder();
+        shaft.begin(start);
+        match self.shaft {
+            ArrowShaft::Straight => {
+                shaft.line_to(end);
+            }
+            ArrowShaft::Quadratic(ctrl) => {
+                shaft.quadratic_bezier_to(ctrl.convert(), end);
+            }
+            ArrowShaft::Cubic(ctrl1, ctrl2) => {
+                shaft.cubic_bezier_to(ctrl1.convert(), ctrl2.convert(), end);
+            }
+        }
+        shaft.end(false);
+
+        let measured = MeasuredPath::new(&shaft.build(), ARROW_TOLERANCE);
+        let shaft = &measured.sub_paths[0];
+        let length = shaft.length();
+
+        let visible = self.start_head.shaft_trim()..length - self.end_head.shaft_trim();
+        if visible.start < visible.end {
+            let mut trimmed = Path::builder_with_attributes(0);
+            shaft.append_range(&mut trimmed, visible, &[]);
+            b.concatenate(&[trimmed.build().as_slice()]);
+        }
+
+        // The markers point from the part of the shaft they cover to the ends.
+        let direction = |from: Point, to: Point| {
+            let direction = to - from;
+            let length = direction.length();
+            if length > 0.0 {
+                direction / length
+            } else {
+                vector(1.0, 0.0)
+            }
+        };
+        let start_direction = direction(shaft.point_at(self.start_head.length()), start);
+        self.start_head.add_geometry(b, start, start_direction);
+        let end_direction = direction(shaft.point_at(length - self.end_head.length()), end);
+        self.end_head.add_geometry(b, end, end_direction);
+    }
+}
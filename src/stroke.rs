@@ -0,0 +1,336 @@
+//! Modifiers applied to the path of a shape before its stroke is tessellated.
+//!
+//! They only affect the geometry produced by
+//! [`TessellationMode::Stroke`](crate::utils::TessellationMode::Stroke) and
+//! the outline of
+//! [`TessellationMode::FillAndStroke`](crate::utils::TessellationMode::FillAndStroke).
+
+use crate::measure::{MeasuredPath, SubPath};
+use bevy::log::warn;
+use lyon_tessellation::path::Path;
+use std::ops::Range;
+
+/// Component that splits the stroke of a shape into dashes.
+///
+/// The lengths are measured along the path, so dashes follow its curves. Like
+/// in SVG, the pattern starts over at the beginning of every sub-path.
+///
+/// A dash of length zero draws a dot with a
+/// [`LineCap::Round`](lyon_tessellation::LineCap::Round) or
+/// [`LineCap::Square`](lyon_tessellation::LineCap::Square) cap.
+///
+/// ```
+/// use bevy_prototype_lyon::prelude::*;
+///
+/// // Dots every 8 units, to be drawn with round caps.
+/// let dotted = StrokeDash::new(vec![0.0, 8.0], 0.0);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StrokeDash {
+    /// The lengths of the alternating dashes and gaps, starting with a dash.
+    ///
+    /// A pattern with an odd number of lengths is repeated to make it even,
+    /// so `[5, 3, 2]` is the same as `[5, 3, 2, 5, 3, 2]`. An empty pattern,
+    /// or one with a negative length or summing to zero, draws a solid
+    /// stroke. So does a pattern shorter than the tolerance of the
+    /// tessellation.
+    pub pattern: Vec<f32>,
+    /// The distance into the pattern at which the path starts.
+    pub offset: f32,
+}
+
+impl StrokeDash {
+    /// Creates a dash pattern.
+    #[must_use]
+    pub const fn new(pattern: Vec<f32>, offset: f32) -> Self {
+        Self { pattern, offset }
+    }
+
+    /// Returns `true` if the pattern draws a solid stroke.
+    #[must_use]
+    pub fn is_solid(&self) -> bool {
+        self.pattern.iter().any(|length| *length < 0.0) || self.pattern.iter().sum::<f32>() <= 0.0
+    }
+
+    /// Returns the dashes of the path, as open sub-paths.
+    ///
+    /// The curves are measured with the given `tolerance`. The path is
+    /// returned unchanged if the pattern is solid.
+    #[must_use]
+    pub fn apply(&self, path: &Path, tolerance: f32) -> Path {
+        stroked_path(path, self, StrokeTrim::default(), tolerance)
+    }
+
+    /// Calls `callback` with the range of every dash of a sub-path of the
+    /// given length.
+    ///
+    /// A pattern shorter than `tolerance` draws a single dash over the whole
+    /// sub-path, and at most [`MAX_DASH_PERIODS`] repetitions of the pattern
+    /// are drawn.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn for_each_dash(&self, length: f32, tolerance: f32, mut callback: impl FnMut(Range<f32>)) {
+        let mut period = self.pattern.iter().sum::<f32>();
+        if self.pattern.len() % 2 == 1 {
+            period *= 2.0;
+        }
+        if period.is_nan() || period < tolerance {
+            callback(0.0..length);
+            return;
+        }
+        let lengths = self.pattern.iter().chain(&self.pattern).copied();
+        let lengths = lengths.take(self.pattern.len() * (1 + self.pattern.len() % 2));
+
+        let start = -self.offset.rem_euclid(period);
+        let mut periods = ((length - start) / period).ceil();
+        if periods > MAX_DASH_PERIODS {
+            warn!(
+                "A dash pattern of length {} repeats too many times over a path of length {}, \
+                 only the first {} repetitions are drawn",
+                period, length, MAX_DASH_PERIODS
+            );
+            periods = MAX_DASH_PERIODS;
+        }
+
+        // Computing the start of every period, instead of adding up the
+        // lengths, keeps the dashes in place despite the rounding errors.
+        for index in 0..periods as usize {
+            let mut distance = (index as f32).mul_add(period, start);
+            for (i, dash_length) in lengths.clone().enumerate() {
+                if i % 2 == 0 {
+                    callback(distance..distance + dash_length);
+                }
+                distance += dash_length;
+            }
+        }
+    }
+}
+
+/// The maximum number of repetitions of a dash pattern along a sub-path.
+const MAX_DASH_PERIODS: f32 = 100_000.0;
+
+/// Component that restricts the stroke of a shape to a part of its path.
+///
+/// `start` and `end` are fractions of the total arc length of the path, from
+/// 0 to 1. Animating them reveals the stroke progressively: the shape is
+/// re-tessellated every time the component changes.
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_prototype_lyon::prelude::*;
+///
+/// // Draws the strokes over two seconds.
+/// fn draw_on(time: Res<Time>, mut query: Query<&mut StrokeTrim>) {
+///     for mut trim in query.iter_mut() {
+///         // Avoid changing the component once the animation is finished.
+///         if trim.end < 1.0 {
+///             trim.end = (trim.end + time.delta_seconds() / 2.0).min(1.0);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrokeTrim {
+    /// The fraction of the path where the stroke starts.
+    pub start: f32,
+    /// The fraction of the path where the stroke ends.
+    pub end: f32,
+}
+
+impl Default for StrokeTrim {
+    fn default() -> Self {
+        Self {
+            start: 0.0,
+            end: 1.0,
+        }
+    }
+}
+
+impl StrokeTrim {
+    /// Creates a trim going from `start` to `end`.
+    #[must_use]
+    pub const fn new(start: f32, end: f32) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns `true` if the trim keeps the whole path.
+    #[must_use]
+    pub fn is_full(self) -> bool {
+        self.start <= 0.0 && self.end >= 1.0
+    }
+
+    /// Returns the part of the path between `start` and `end`.
+    ///
+    /// The curves are measured with the given `tolerance`. The path is
+    /// returned unchanged if the trim is full.
+    #[must_use]
+    pub fn apply(self, path: &Path, tolerance: f32) -> Path {
+        stroked_path(path, &StrokeDash::default(), self, tolerance)
+    }
+}
+
+/// Returns the path whose stroke is drawn, with the given dash pattern and
+/// trim.
+///
+/// The dashes are laid out over the whole path, so they don't move when the
+/// trim changes.
+fn stroked_path(path: &Path, dash: &StrokeDash, trim: StrokeTrim, tolerance: f32) -> Path {
+    if dash.is_solid() && trim.is_full() {
+        return path.clone();
+    }
+
+    let measured = MeasuredPath::new(path, tolerance);
+    let mut builder = Path::builder_with_attributes(measured.num_attributes);
+    for_each_piece(&measured, dash, trim, tolerance, |sub_path, range| {
+        sub_path.append_range(&mut builder, range, &[]);
+    });
+
+    builder.build()
+}
+
+/// Returns the path whose stroke is drawn, like [`stroked_path`], with the
+/// length of the longest sub-path of `path`.
+///
+/// The stroked path has one more attribute than `path`: the distance from the
+/// beginning of the sub-path of `path` to the start of the dash or trimmed
+/// part, which is added to the stroke advancement to compute texture
+/// coordinates that don't restart with every piece.
+pub(crate) fn stroked_path_with_distances(
+    path: &Path,
+    dash: &StrokeDash,
+    trim: StrokeTrim,
+    tolerance: f32,
+) -> (Path, f32) {
+    let measured = MeasuredPath::new(path, tolerance);
+    let mut builder = Path::builder_with_attributes(measured.num_attributes + 1);
+    for_each_piece(&measured, dash, trim, tolerance, |sub_path, range| {
+        let start = range.start.max(0.0);
+        sub_path.append_range(&mut builder, range, &[start]);
+    });
+    let longest = measured
+        .sub_paths
+        .iter()
+        .map(SubPath::length)
+        .fold(0.0, f32::max);
+
+    (builder.build(), longest)
+}
+
+/// Calls `callback` with the sub-paths of `measured` and the ranges of their
+/// visible pieces, relative to their beginning.
+fn for_each_piece(
+    measured: &MeasuredPath,
+    dash: &StrokeDash,
+    trim: StrokeTrim,
+    tolerance: f32,
+    mut callback: impl FnMut(&SubPath, Range<f32>),
+) {
+    let length = measured.length();
+    let mut sub_path_start = 0.0;
+    for sub_path in &measured.sub_paths {
+        // The visible range, relative to the start of the sub-path.
+        let visible = if trim.is_full() {
+            f32::NEG_INFINITY..f32::INFINITY
+        } else {
+            trim.start.mul_add(length, -sub_path_start)..trim.end.mul_add(length, -sub_path_start)
+        };
+        let mut append = |range: Range<f32>| {
+            if let Some(range) = intersect(range, &visible) {
+                callback(sub_path, range);
+            }
+        };
+
+        if dash.is_solid() {
+            append(0.0..sub_path.length());
+        } else {
+            dash.for_each_dash(sub_path.length(), tolerance, append);
+        }
+        sub_path_start += sub_path.length();
+    }
+}
+
+/// Returns the part of `range` inside `visible`.
+///
+/// An empty range, drawn as a dot, is kept if it is inside `visible`, but
+/// ranges that only touch `visible` are hidden.
+fn intersect(range: Range<f32>, visible: &Range<f32>) -> Option<Range<f32>> {
+    let start = range.start.max(visible.start);
+    let end = range.end.min(visible.end);
+    if start < end || (start <= end && range.end <= range.start) {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lyon_tessellation::{math::Point, path::Event};
+
+    fn dashes(pattern: &[f32], offset: f32, length: f32) -> Vec<Range<f32>> {
+        let dash = StrokeDash::new(pattern.to_vec(), offset);
+        let mut dashes = Vec::new();
+        dash.for_each_dash(length, 0.1, |range| dashes.push(range));
+        dashes
+    }
+
+    #[test]
+    fn dashes_cover_the_sub_path() {
+        assert_eq!(dashes(&[5.0, 3.0], 0.0, 16.0), vec![0.0..5.0, 8.0..13.0]);
+        assert_eq!(
+            dashes(&[5.0, 3.0], 2.0, 16.0),
+            vec![-2.0..3.0, 6.0..11.0, 14.0..19.0]
+        );
+        assert_eq!(
+            dashes(&[5.0, 3.0, 2.0], 0.0, 20.0),
+            vec![0.0..5.0, 8.0..10.0, 15.0..18.0]
+        );
+    }
+
+    #[test]
+    fn short_patterns_draw_a_solid_stroke() {
+        assert_eq!(dashes(&[0.01, 0.01], 0.0, 50.0), vec![0.0..50.0]);
+        assert_eq!(dashes(&[1e-30, 1e-30], 3.0, 50.0), vec![0.0..50.0]);
+        assert_eq!(dashes(&[f32::NAN, 1.0], 0.0, 50.0), vec![0.0..50.0]);
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn long_paths_are_bounded() {
+        let dashes = dashes(&[1.0, 1.0], 0.0, 1e12);
+        assert_eq!(dashes.len(), MAX_DASH_PERIODS as usize);
+        assert!(dashes.windows(2).all(|pair| pair[0].start < pair[1].start));
+    }
+
+    #[test]
+    fn pieces_record_their_start_distance() {
+        let mut builder = Path::builder();
+        builder.begin(Point::new(0.0, 0.0));
+        builder.line_to(Point::new(16.0, 0.0));
+        builder.end(false);
+        builder.begin(Point::new(0.0, 10.0));
+        builder.line_to(Point::new(4.0, 10.0));
+        builder.end(false);
+        let path = builder.build();
+
+        let dash = StrokeDash::new(vec![5.0, 3.0], 0.0);
+        let trim = StrokeTrim::new(0.1, 1.0);
+        let (stroked, longest) = stroked_path_with_distances(&path, &dash, trim, 0.1);
+        assert!((longest - 16.0).abs() < 1e-4);
+
+        let starts: Vec<_> = stroked
+            .iter_with_attributes()
+            .filter_map(|event| match event {
+                Event::Begin {
+                    at: (at, attributes),
+                } => Some((at.x, attributes[0])),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(starts, vec![(2.0, 2.0), (8.0, 8.0), (0.0, 0.0)]);
+    }
+}
@@ -0,0 +1,531 @@
+//! Parser for the SVG path data syntax.
+//!
+//! The grammar is the one used by the `d` attribute of the SVG `<path>`
+//! element. See the [SVG specification](https://www.w3.org/TR/SVG11/paths.html#PathData)
+//! for the details.
+
+use lyon_tessellation::{
+    math::{point, vector, Angle, Vector},
+    path::{traits::SvgPathBuilder, ArcFlags},
+};
+use std::{error::Error, fmt};
+
+/// The reason why some SVG path data could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SvgPathErrorKind {
+    /// The path data does not start with a move command (`M` or `m`).
+    MissingMoveTo,
+    /// A character that is not a command was found where a command was
+    /// expected.
+    UnexpectedCharacter(char),
+    /// A number was expected.
+    ExpectedNumber,
+    /// An arc flag (`0` or `1`) was expected.
+    ExpectedFlag,
+    /// The path data ended in the middle of a command.
+    UnexpectedEnd,
+}
+
+/// An error that occurred while parsing SVG path data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgPathError {
+    /// What went wrong.
+    pub kind: SvgPathErrorKind,
+    /// The byte offset in the path data where the error occurred.
+    pub offset: usize,
+}
+
+impl fmt::Display for SvgPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            SvgPathErrorKind::MissingMoveTo => write!(
+                f,
+                "path data must start with a move command (byte {})",
+                self.offset
+            ),
+            SvgPathErrorKind::UnexpectedCharacter(c) => write!(
+                f,
+                "expected a command, found {:?} at byte {}",
+                c, self.offset
+            ),
+            SvgPathErrorKind::ExpectedNumber => {
+                write!(f, "expected a number at byte {}", self.offset)
+            }
+            SvgPathErrorKind::ExpectedFlag => {
+                write!(f, "expected an arc flag (0 or 1) at byte {}", self.offset)
+            }
+            SvgPathErrorKind::UnexpectedEnd => {
+                write!(f, "unexpected end of path data at byte {}", self.offset)
+            }
+        }
+    }
+}
+
+impl Error for SvgPathError {}
+
+/// Parses a SVG path data string, forwarding every command to the given
+/// builder.
+///
+/// All the absolute and relative commands are supported: `M`, `L`, `H`, `V`,
+/// `C`, `S`, `Q`, `T`, `A` and `Z`.
+///
+/// # Errors
+///
+/// Returns a [`SvgPathError`] describing the first malformed part of the path
+/// data. The commands preceding the error have already been forwarded to the
+/// builder.
+pub fn parse_path_data(data: &str, builder: &mut impl SvgPathBuilder) -> Result<(), SvgPathError> {
+    let mut parser = Parser { data, pos: 0 };
+
+    parser.skip_whitespace();
+    let mut first = true;
+    while let Some(c) = parser.peek() {
+        let offset = parser.pos;
+        if !is_command(c) {
+            return Err(parser.error_at(SvgPathErrorKind::UnexpectedCharacter(c), offset));
+        }
+        if first && c != 'M' && c != 'm' {
+            return Err(parser.error_at(SvgPathErrorKind::MissingMoveTo, offset));
+        }
+        first = false;
+        parser.pos += 1;
+
+        if c == 'Z' || c == 'z' {
+            builder.close();
+        } else {
+            let mut command = c;
+            loop {
+                parser.command_arguments(command, builder)?;
+                // Coordinate pairs following a move command are implicit line commands.
+                command = match command {
+                    'M' => 'L',
+                    'm' => 'l',
+                    c => c,
+                };
+                if !parser.at_number() {
+                    break;
+                }
+            }
+        }
+
+        parser.skip_whitespace();
+    }
+
+    Ok(())
+}
+
+/// Parses a list of numbers separated by whitespace and/or commas, like the
+/// `points` attribute of a `<polygon>` element.
+pub(crate) fn parse_number_list(data: &str) -> Result<Vec<f32>, SvgPathError> {
+    let mut parser = Parser { data, pos: 0 };
+    let mut numbers = Vec::new();
+
+    parser.skip_whitespace();
+    while parser.peek().is_some() {
+        numbers.push(parser.number()?);
+    }
+
+    Ok(numbers)
+}
+
+fn is_command(c: char) -> bool {
+    "MmZzLlHhVvCcSsQqTtAa".contains(c)
+}
+
+const fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0C')
+}
+
+struct Parser<'a> {
+    data: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.data[self.pos..].chars().next()
+    }
+
+    fn error_at(&self, kind: SvgPathErrorKind, offset: usize) -> SvgPathError {
+        let kind = match kind {
+            SvgPathErrorKind::ExpectedNumber | SvgPathErrorKind::ExpectedFlag
+                if offset >= self.data.len() =>
+            {
+                SvgPathErrorKind::UnexpectedEnd
+            }
+            kind => kind,
+        };
+
+        SvgPathError { kind, offset }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !is_whitespace(c) {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skips the optional whitespace and comma between two arguments.
+    fn skip_separator(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    /// Returns `true` if the next character can start a number.
+    fn at_number(&self) -> bool {
+        matches!(self.peek(), Some(c) if c.is_ascii_digit() || matches!(c, '+' | '-' | '.'))
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Result<f32, SvgPathError> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        if matches!(self.peek(), Some('+') | Some('-')) {
+            self.pos += 1;
+        }
+        let mut digits = self.skip_digits();
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            digits += self.skip_digits();
+        }
+        if digits == 0 {
+            self.pos = start;
+            return Err(self.error_at(SvgPathErrorKind::ExpectedNumber, start));
+        }
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if matches!(self.peek(), Some('+') | Some('-')) {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.pos = mantissa_end;
+            }
+        }
+
+        let number = self.data[start..self.pos]
+            .parse::<f32>()
+            .map_err(|_| self.error_at(SvgPathErrorKind::ExpectedNumber, start))?;
+        self.skip_separator();
+
+        Ok(number)
+    }
+
+    fn flag(&mut self) -> Result<bool, SvgPathError> {
+        self.skip_whitespace();
+        let flag = match self.peek() {
+            Some('0') => false,
+            Some('1') => true,
+            _ => return Err(self.error_at(SvgPathErrorKind::ExpectedFlag, self.pos)),
+        };
+        self.pos += 1;
+        self.skip_separator();
+
+        Ok(flag)
+    }
+
+    fn vector(&mut self) -> Result<Vector, SvgPathError> {
+        let x = self.number()?;
+        let y = self.number()?;
+
+        Ok(vector(x, y))
+    }
+
+    /// Parses the arguments of a single command and forwards it to the
+    /// builder.
+    fn command_arguments(
+        &mut self,
+        command: char,
+        builder: &mut impl SvgPathBuilder,
+    ) -> Result<(), SvgPathError> {
+        match command {
+            'M' => builder.move_to(self.vector()?.to_point()),
+            'm' => builder.relative_move_to(self.vector()?),
+            'L' => builder.line_to(self.vector()?.to_point()),
+            'l' => builder.relative_line_to(self.vector()?),
+            'H' => builder.horizontal_line_to(self.number()?),
+            'h' => builder.relative_horizontal_line_to(self.number()?),
+            'V' => builder.vertical_line_to(self.number()?),
+            'v' => builder.relative_vertical_line_to(self.number()?),
+            'A' | 'a' => self.arc_arguments(command == 'a', builder)?,
+            _ => self.curve_arguments(command, builder)?,
+        }
+
+        Ok(())
+    }
+
+    /// Parses the arguments of a Bézier curve command and forwards it to the
+    /// builder.
+    fn curve_arguments(
+        &mut self,
+        command: char,
+        builder: &mut impl SvgPathBuilder,
+    ) -> Result<(), SvgPathError> {
+        match command {
+            'C' => {
+                let ctrl1 = self.vector()?.to_point();
+                let ctrl2 = self.vector()?.to_point();
+                let to = self.vector()?.to_point();
+                builder.cubic_bezier_to(ctrl1, ctrl2, to);
+            }
+            'c' => {
+                let ctrl1 = self.vector()?;
+                let ctrl2 = self.vector()?;
+                let to = self.vector()?;
+                builder.relative_cubic_bezier_to(ctrl1, ctrl2, to);
+            }
+            'S' => {
+                let ctrl2 = self.vector()?.to_point();
+                let to = self.vector()?.to_point();
+                builder.smooth_cubic_bezier_to(ctrl2, to);
+            }
+            's' => {
+                let ctrl2 = self.vector()?;
+                let to = self.vector()?;
+                builder.smooth_relative_cubic_bezier_to(ctrl2, to);
+            }
+            'Q' => {
+                let ctrl = self.vector()?.to_point();
+                let to = self.vector()?.to_point();
+                builder.quadratic_bezier_to(ctrl, to);
+            }
+            'q' => {
+                let ctrl = self.vector()?;
+                let to = self.vector()?;
+                builder.relative_quadratic_bezier_to(ctrl, to);
+            }
+            'T' => builder.smooth_quadratic_bezier_to(self.vector()?.to_point()),
+            't' => builder.smooth_relative_quadratic_bezier_to(self.vector()?),
+            _ => unreachable!("not a command with arguments: {:?}", command),
+        }
+
+        Ok(())
+    }
+
+    /// Parses the arguments of an elliptical arc command and forwards it to
+    /// the builder.
+    fn arc_arguments(
+        &mut self,
+        relative: bool,
+        builder: &mut impl SvgPathBuilder,
+    ) -> Result<(), SvgPathError> {
+        let radii = self.vector()?;
+        let x_rotation = Angle::degrees(self.number()?);
+        let flags = ArcFlags {
+            large_arc: self.flag()?,
+            sweep: self.flag()?,
+        };
+        let to = self.vector()?;
+        let radii = vector(radii.x.abs(), radii.y.abs());
+        if relative {
+            builder.relative_arc_to(radii, x_rotation, flags, to);
+        } else {
+            builder.arc_to(radii, x_rotation, flags, point(to.x, to.y));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lyon_tessellation::{
+        math::Point,
+        path::{Path, PathEvent},
+    };
+
+    fn parse(data: &str) -> Result<Vec<PathEvent>, SvgPathError> {
+        let mut builder = Path::builder().with_svg();
+        parse_path_data(data, &mut builder)?;
+
+        Ok(builder.build().iter().collect())
+    }
+
+    fn error(data: &str) -> (SvgPathErrorKind, usize) {
+        let error = parse(data).expect_err("the path data should be rejected");
+
+        (error.kind, error.offset)
+    }
+
+    /// Returns the end points of the segments of the path.
+    fn end_points(data: &str) -> Vec<Point> {
+        parse(data)
+            .expect("the path data should be valid")
+            .into_iter()
+            .filter_map(|event| match event {
+                PathEvent::Begin { at } => Some(at),
+                PathEvent::Line { to, .. }
+                | PathEvent::Quadratic { to, .. }
+                | PathEvent::Cubic { to, .. } => Some(to),
+                PathEvent::End { .. } => None,
+            })
+            .collect()
+    }
+
+    fn assert_near(actual: Point, expected: Point) {
+        assert!(
+            (actual - expected).length() < 1e-4,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn malformed_numbers() {
+        assert_eq!(error("M 10 x"), (SvgPathErrorKind::ExpectedNumber, 5));
+        assert_eq!(error("M 10 - 5"), (SvgPathErrorKind::ExpectedNumber, 5));
+        assert_eq!(error("M 1e 2"), (SvgPathErrorKind::ExpectedNumber, 3));
+        assert_eq!(error("M 0 0 L . 1"), (SvgPathErrorKind::ExpectedNumber, 8));
+    }
+
+    #[test]
+    fn missing_move_to() {
+        assert_eq!(error("L 10 10"), (SvgPathErrorKind::MissingMoveTo, 0));
+        assert_eq!(error("  z"), (SvgPathErrorKind::MissingMoveTo, 2));
+    }
+
+    #[test]
+    fn unexpected_characters() {
+        assert_eq!(
+            error("M 0 0 X 1"),
+            (SvgPathErrorKind::UnexpectedCharacter('X'), 6)
+        );
+        assert_eq!(
+            error("10 10"),
+            (SvgPathErrorKind::UnexpectedCharacter('1'), 0)
+        );
+        assert_eq!(
+            error("M 0 0 A 1 1 0 2 0 1 1"),
+            (SvgPathErrorKind::ExpectedFlag, 14)
+        );
+    }
+
+    #[test]
+    fn truncated_commands() {
+        assert_eq!(error("M 10 10 L 20"), (SvgPathErrorKind::UnexpectedEnd, 12));
+        assert_eq!(error("M"), (SvgPathErrorKind::UnexpectedEnd, 1));
+        assert_eq!(
+            error("M 0 0 C 1 1 2 2 "),
+            (SvgPathErrorKind::UnexpectedEnd, 16)
+        );
+        assert_eq!(
+            error("M 0 0 A 1 1 0 1"),
+            (SvgPathErrorKind::UnexpectedEnd, 15)
+        );
+    }
+
+    #[test]
+    fn errors_keep_previous_commands() {
+        let mut builder = Path::builder().with_svg();
+        let result = parse_path_data("M 0 0 L 10 0 L 10", &mut builder);
+        assert_eq!(
+            result.map_err(|error| error.kind),
+            Err(SvgPathErrorKind::UnexpectedEnd)
+        );
+        let events = builder.build().iter().collect::<Vec<_>>();
+        assert!(matches!(events[1], PathEvent::Line { to, .. } if to == point(10.0, 0.0)));
+    }
+
+    #[test]
+    fn implicit_line_to() {
+        assert_eq!(
+            end_points("M 0 0 10 0 10 10 z"),
+            [point(0.0, 0.0), point(10.0, 0.0), point(10.0, 10.0)]
+        );
+        assert_eq!(
+            end_points("m 1 1 2 0 0 2"),
+            [point(1.0, 1.0), point(3.0, 1.0), point(3.0, 3.0)]
+        );
+        assert_eq!(
+            end_points("M0,0L1,1,2,2"),
+            [point(0.0, 0.0), point(1.0, 1.0), point(2.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn compact_numbers() {
+        assert_eq!(
+            end_points("M-1-2L.5.5l1e1-1E-1"),
+            [point(-1.0, -2.0), point(0.5, 0.5), point(10.5, 0.4)]
+        );
+    }
+
+    #[test]
+    fn compact_arc_flags() {
+        let points = end_points("M 0 0 a1 1 0 00 1 1");
+        assert_near(*points.last().unwrap(), point(1.0, 1.0));
+        let points = end_points("M 0 0 a1 1 0 011 1");
+        assert_near(*points.last().unwrap(), point(1.0, 1.0));
+        let points = end_points("M 2 0 A1,1,0,1,1,0,0");
+        assert_near(*points.last().unwrap(), point(0.0, 0.0));
+    }
+
+    #[test]
+    fn absolute_and_relative_commands() {
+        assert_eq!(
+            end_points("M 10 10 l 5 0 L 20 20 h 5 H 0 v -5 V 30"),
+            [
+                point(10.0, 10.0),
+                point(15.0, 10.0),
+                point(20.0, 20.0),
+                point(25.0, 20.0),
+                point(0.0, 20.0),
+                point(0.0, 15.0),
+                point(0.0, 30.0),
+            ]
+        );
+        assert_eq!(
+            end_points("M 0 0 c 1 1 2 1 3 0 C 4 -1 5 -1 6 0 s 1 1 2 0 S 9 1 10 0"),
+            [
+                point(0.0, 0.0),
+                point(3.0, 0.0),
+                point(6.0, 0.0),
+                point(8.0, 0.0),
+                point(10.0, 0.0),
+            ]
+        );
+        assert_eq!(
+            end_points("M 0 0 q 1 1 2 0 Q 3 -1 4 0 t 2 0 T 8 0"),
+            [
+                point(0.0, 0.0),
+                point(2.0, 0.0),
+                point(4.0, 0.0),
+                point(6.0, 0.0),
+                point(8.0, 0.0),
+            ]
+        );
+        assert_eq!(
+            end_points("M 1 1 z m 2 2 l 1 0"),
+            [point(1.0, 1.0), point(3.0, 3.0), point(4.0, 3.0)]
+        );
+    }
+
+    #[test]
+    fn number_lists() {
+        assert_eq!(
+            parse_number_list(" 1,2 3.5 -4e1 "),
+            Ok(vec![1.0, 2.0, 3.5, -40.0])
+        );
+        assert_eq!(
+            parse_number_list("1, x").map_err(|error| error.offset),
+            Err(3)
+        );
+    }
+}
@@ -0,0 +1,1138 @@
+//! Loading of whole SVG files as Bevy assets.
+//!
+//! When the `svg` feature is enabled (it is by default), the
+//! [`ShapePlugin`](crate::plugin::ShapePlugin) registers an asset loader for
+//! `.svg` files. The loaded [`Svg`] asset can then be spawned with a
+//! [`SvgBundle`]: every drawable element of the document becomes a
+//! [`ShapeBundle`] child of the entity. When the file is hot-reloaded, the
+//! children are spawned again to reflect the new content.
+//!
+//! Only a subset of SVG is supported: groups, the `path`, `rect`, `circle`,
+//! `ellipse`, `line`, `polyline` and `polygon` elements, solid fill and stroke
+//! colors with their opacities, the stroke width, caps and joins, the fill
+//! rule, the `transform` attribute and the `viewBox` of the root element.
+//! Everything else (gradients, text, images, `use` references, ...) is ignored.
+
+use crate::{
+    entity::{OutlineMaterial, ShapeBundle},
+    svg::{parse_number_list, parse_path_data, SvgPathError},
+    utils::TessellationMode,
+};
+use bevy::{
+    app::{EventReader, Events},
+    asset::{AssetEvent, AssetLoader, Assets, Handle, HandleId, LoadContext, LoadedAsset},
+    ecs::{Bundle, Commands, Entity, Local, Query, Res, ResMut},
+    log::warn,
+    math::Vec3,
+    reflect::TypeUuid,
+    render::color::Color,
+    sprite::ColorMaterial,
+    transform::{
+        components::{GlobalTransform, Transform},
+        hierarchy::{BuildChildren, DespawnRecursiveExt},
+    },
+    utils::{BoxedFuture, HashSet},
+};
+use lyon_tessellation::{
+    math::{point, vector, Angle, Point, Rect, Size, Transform as Transform2D},
+    path::{
+        traits::{PathBuilder, SvgPathBuilder},
+        ArcFlags, Path, Polygon, Winding,
+    },
+    FillOptions, FillRule, LineCap, LineJoin, StrokeOptions,
+};
+use std::{error::Error, fmt};
+
+/// Distance on the z axis between two consecutive shapes of a [`Svg`], so
+/// that they are drawn in document order.
+const SHAPE_Z_STEP: f32 = 0.01;
+
+/// An error that occurred while parsing a SVG document.
+#[derive(Debug)]
+pub enum SvgError {
+    /// The document is not well-formed XML.
+    Xml(roxmltree::Error),
+    /// The `d` attribute of a `<path>` element could not be parsed.
+    PathData(SvgPathError),
+    /// An attribute or style property has a value that could not be parsed.
+    InvalidAttribute {
+        /// The name of the attribute.
+        name: String,
+        /// The value that could not be parsed.
+        value: String,
+    },
+}
+
+impl fmt::Display for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Xml(e) => write!(f, "invalid XML: {}", e),
+            Self::PathData(e) => write!(f, "invalid path data: {}", e),
+            Self::InvalidAttribute { name, value } => {
+                write!(f, "invalid value {:?} for attribute `{}`", value, name)
+            }
+        }
+    }
+}
+
+impl Error for SvgError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Xml(e) => Some(e),
+            Self::PathData(e) => Some(e),
+            Self::InvalidAttribute { .. } => None,
+        }
+    }
+}
+
+impl From<roxmltree::Error> for SvgError {
+    fn from(e: roxmltree::Error) -> Self {
+        Self::Xml(e)
+    }
+}
+
+impl From<SvgPathError> for SvgError {
+    fn from(e: SvgPathError) -> Self {
+        Self::PathData(e)
+    }
+}
+
+fn invalid_attribute(name: &str, value: &str) -> SvgError {
+    SvgError::InvalidAttribute {
+        name: name.to_owned(),
+        value: value.to_owned(),
+    }
+}
+
+/// A single drawable element of a [`Svg`] document.
+#[derive(Debug, Clone)]
+pub struct SvgShape {
+    /// The outline of the element, with all the transforms of the document
+    /// already applied. The y axis points up, like in Bevy.
+    pub path: Path,
+    /// The fill color, or `None` if the element is not filled.
+    pub fill: Option<Color>,
+    /// The options used to fill the element.
+    pub fill_options: FillOptions,
+    /// The stroke color, or `None` if the element is not stroked.
+    pub stroke: Option<Color>,
+    /// The options used to stroke the element.
+    pub stroke_options: StrokeOptions,
+}
+
+impl SvgShape {
+    /// Returns the [`TessellationMode`] needed to draw the element, or `None`
+    /// if it is neither filled nor stroked.
+    #[must_use]
+    pub fn mode(&self) -> Option<TessellationMode> {
+        match (self.fill, self.stroke) {
+            (Some(_), Some(_)) => Some(TessellationMode::FillAndStroke(
+                self.fill_options,
+                self.stroke_options,
+            )),
+            (Some(_), None) => Some(TessellationMode::Fill(self.fill_options)),
+            (None, Some(_)) => Some(TessellationMode::Stroke(self.stroke_options)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// A SVG document, loaded from a `.svg` file by the [`AssetServer`].
+///
+/// [`AssetServer`]: bevy::asset::AssetServer
+#[derive(Debug, Clone, Default, TypeUuid)]
+#[uuid = "7d5c2a5e-3b8f-4f0e-9c4d-2f6a1b3e8d71"]
+pub struct Svg {
+    /// The drawable elements of the document, in drawing order.
+    pub shapes: Vec<SvgShape>,
+}
+
+impl Svg {
+    /// Parses the text of a SVG document.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SvgError`] if the document is not well-formed XML, or if
+    /// the path data of an element, a `transform` or the `viewBox` is
+    /// malformed.
+    ///
+    /// The unsupported values of the style properties are ignored, and the
+    /// elements with an unsupported geometry attribute are skipped, with a
+    /// warning.
+    pub fn parse(text: &str) -> Result<Self, SvgError> {
+        let document = roxmltree::Document::parse(text)?;
+        let root = document.root_element();
+        let style = Style::default().inherit(root);
+        // SVG coordinates grow downwards, Bevy ones upwards.
+        let transform = view_box_transform(root)?.then(&Transform2D::scale(1.0, -1.0));
+
+        let mut shapes = Vec::new();
+        parse_children(root, &style, &transform, &mut shapes)?;
+
+        Ok(Self { shapes })
+    }
+}
+
+/// Loads [`Svg`] assets from `.svg` files.
+#[derive(Debug, Default)]
+pub struct SvgLoader;
+
+impl AssetLoader for SvgLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let svg = Svg::parse(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(svg));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["svg"]
+    }
+}
+
+/// A Bevy [`Bundle`] that draws a [`Svg`] asset.
+///
+/// Once the asset is loaded, a [`ShapeBundle`] child entity is spawned for
+/// each of its shapes.
+#[allow(missing_docs)]
+#[derive(Bundle, Default)]
+pub struct SvgBundle {
+    pub svg: Handle<Svg>,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+}
+
+/// Keeps track of the shape entities spawned for a [`Svg`] asset.
+pub(crate) struct SvgInstance {
+    handle: HandleId,
+    shapes: Vec<Entity>,
+}
+
+/// A bevy system. Spawns the shapes of the [`Svg`] assets as children of the
+/// entities holding their handle, and spawns them again when the asset is
+/// modified.
+///
+/// It runs in the [`UPDATE`](bevy::app::stage::UPDATE) stage, so that the
+/// spawned shapes are completed in the same frame.
+pub(crate) fn spawn_svg_shapes(
+    commands: &mut Commands,
+    mut event_reader: Local<EventReader<AssetEvent<Svg>>>,
+    events: Res<Events<AssetEvent<Svg>>>,
+    svgs: Res<Assets<Svg>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    query: Query<(Entity, &Handle<Svg>, Option<&SvgInstance>)>,
+) {
+    let mut modified = HashSet::default();
+    for event in event_reader.iter(&events) {
+        if let AssetEvent::Modified { handle } = event {
+            modified.insert(handle.id);
+        }
+    }
+
+    for (entity, handle, instance) in query.iter() {
+        let up_to_date = instance.map_or(false, |instance| {
+            instance.handle == handle.id && !modified.contains(&handle.id)
+        });
+        if up_to_date {
+            continue;
+        }
+        let svg = match svgs.get(handle) {
+            Some(svg) => svg,
+            None => continue,
+        };
+
+        if let Some(instance) = instance {
+            for &shape in &instance.shapes {
+                commands.despawn_recursive(shape);
+            }
+        }
+
+        let shapes = spawn_shapes(commands, svg, &mut materials);
+        commands.push_children(entity, &shapes).insert_one(
+            entity,
+            SvgInstance {
+                handle: handle.id,
+                shapes,
+            },
+        );
+    }
+}
+
+/// Spawns the shapes of an [`Svg`], from back to front, and returns their
+/// entities.
+fn spawn_shapes(
+    commands: &mut Commands,
+    svg: &Svg,
+    materials: &mut Assets<ColorMaterial>,
+) -> Vec<Entity> {
+    let mut shapes = Vec::with_capacity(svg.shapes.len());
+    for (index, shape) in svg.shapes.iter().enumerate() {
+        let z = index as f32 * SHAPE_Z_STEP;
+        if let Some(bundle) = shape_bundle(shape, z, materials) {
+            let child = commands
+                .spawn(bundle)
+                .current_entity()
+                .expect("the shape entity has just been spawned");
+            shapes.push(child);
+        }
+    }
+
+    shapes
+}
+
+fn shape_bundle(
+    shape: &SvgShape,
+    z: f32,
+    materials: &mut Assets<ColorMaterial>,
+) -> Option<ShapeBundle> {
+    let mode = shape.mode()?;
+    let (material, outline_material) = match (shape.fill, shape.stroke) {
+        (Some(fill), Some(stroke)) => (
+            materials.add(ColorMaterial::color(fill)),
+            materials.add(ColorMaterial::color(stroke)),
+        ),
+        (Some(color), None) | (None, Some(color)) => (
+            materials.add(ColorMaterial::color(color)),
+            Handle::default(),
+        ),
+        (None, None) => return None,
+    };
+
+    Some(ShapeBundle {
+        path: shape.path.clone(),
+        mode,
+        material,
+        outline_material: OutlineMaterial(outline_material),
+        transform: Transform::from_translation(Vec3::new(0.0, 0.0, z)),
+        ..ShapeBundle::default()
+    })
+}
+
+/// A fill or stroke paint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Paint {
+    None,
+    /// A RGBA color.
+    Color([f32; 4]),
+    /// The value of the `color` property of the element.
+    CurrentColor,
+}
+
+/// The presentation attributes of an element, as inherited from its
+/// ancestors.
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    color: [f32; 4],
+    fill: Paint,
+    fill_opacity: f32,
+    fill_rule: FillRule,
+    stroke: Paint,
+    stroke_opacity: f32,
+    stroke_width: f32,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    miter_limit: f32,
+    opacity: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            color: [0.0, 0.0, 0.0, 1.0],
+            fill: Paint::Color([0.0, 0.0, 0.0, 1.0]),
+            fill_opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            stroke: Paint::None,
+            stroke_opacity: 1.0,
+            stroke_width: 1.0,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: StrokeOptions::DEFAULT_MITER_LIMIT,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Style {
+    /// Returns the style of `node`, whose parent has the style `self`.
+    ///
+    /// Properties in the `style` attribute take precedence over the
+    /// presentation attributes.
+    fn inherit(&self, node: roxmltree::Node<'_, '_>) -> Self {
+        let mut style = *self;
+        // The opacity is not inherited: the opacity of the element replaces
+        // its default value, then group opacity is approximated by
+        // multiplying it into the opacity of the descendants.
+        style.opacity = 1.0;
+        for attribute in node.attributes() {
+            style.set(attribute.name(), attribute.value());
+        }
+        if let Some(declarations) = node.attribute("style") {
+            for declaration in declarations.split(';') {
+                let mut parts = declaration.splitn(2, ':');
+                if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+                    style.set(name.trim(), value.trim());
+                }
+            }
+        }
+        style.opacity *= self.opacity;
+
+        style
+    }
+
+    /// Sets a property, or warns and keeps its current value if its value is
+    /// not supported.
+    fn set(&mut self, name: &str, value: &str) {
+        if value == "inherit" {
+            return;
+        }
+
+        let parsed = match name {
+            "color" => parse_color(value).map(|color| self.color = color),
+            "fill" => parse_paint(name, value).map(|paint| self.fill = paint),
+            "fill-opacity" => parse_opacity(value).map(|opacity| self.fill_opacity = opacity),
+            "fill-rule" => match value {
+                "nonzero" => Some(FillRule::NonZero),
+                "evenodd" => Some(FillRule::EvenOdd),
+                _ => None,
+            }
+            .map(|fill_rule| self.fill_rule = fill_rule),
+            "stroke" => parse_paint(name, value).map(|paint| self.stroke = paint),
+            "stroke-opacity" => parse_opacity(value).map(|opacity| self.stroke_opacity = opacity),
+            "stroke-width" => parse_length(value).map(|width| self.stroke_width = width),
+            "stroke-linecap" => match value {
+                "butt" => Some(LineCap::Butt),
+                "round" => Some(LineCap::Round),
+                "square" => Some(LineCap::Square),
+                _ => None,
+            }
+            .map(|line_cap| self.line_cap = line_cap),
+            "stroke-linejoin" => match value {
+                "miter" | "arcs" => Some(LineJoin::Miter),
+                "miter-clip" => Some(LineJoin::MiterClip),
+                "round" => Some(LineJoin::Round),
+                "bevel" => Some(LineJoin::Bevel),
+                _ => None,
+            }
+            .map(|line_join| self.line_join = line_join),
+            // Lyon panics with miter limits lower than 1.
+            "stroke-miterlimit" => {
+                parse_number(value).map(|limit| self.miter_limit = limit.max(1.0))
+            }
+            "opacity" => parse_opacity(value).map(|opacity| self.opacity = opacity),
+            _ => Some(()),
+        };
+        if parsed.is_none() {
+            warn!(
+                "unsupported value {:?} for the SVG property `{}`, it is ignored",
+                value, name
+            );
+        }
+    }
+
+    /// Returns the color of a paint of the element, with the given opacity.
+    fn paint_color(&self, paint: Paint, opacity: f32) -> Option<Color> {
+        let [r, g, b, a] = match paint {
+            Paint::None => return None,
+            Paint::Color(color) => color,
+            Paint::CurrentColor => self.color,
+        };
+
+        Some(Color::rgba(r, g, b, a * opacity * self.opacity))
+    }
+}
+
+/// Walks the children of `node`, appending their shapes to `shapes`.
+fn parse_children(
+    node: roxmltree::Node<'_, '_>,
+    style: &Style,
+    transform: &Transform2D,
+    shapes: &mut Vec<SvgShape>,
+) -> Result<(), SvgError> {
+    for child in node.children().filter(roxmltree::Node::is_element) {
+        if child.attribute("display") == Some("none") {
+            continue;
+        }
+        let style = style.inherit(child);
+        let transform = match child.attribute("transform") {
+            Some(value) => parse_transform(value)?.then(transform),
+            None => *transform,
+        };
+
+        match child.tag_name().name() {
+            "g" | "a" => parse_children(child, &style, &transform, shapes)?,
+            _ => match element_path(child) {
+                Ok(Some(path)) => shapes.push(svg_shape(path, &style, &transform)),
+                Ok(None) => {}
+                Err(SvgError::InvalidAttribute { name, value }) => warn!(
+                    "unsupported value {:?} for the SVG attribute `{}`, the <{}> element is ignored",
+                    value,
+                    name,
+                    child.tag_name().name()
+                ),
+                Err(error) => return Err(error),
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn svg_shape(path: Path, style: &Style, transform: &Transform2D) -> SvgShape {
+    // Stroke widths are scaled by the average scale factor of the transform.
+    let scale = transform.determinant().abs().sqrt();
+
+    SvgShape {
+        path: path.transformed(transform),
+        fill: style.paint_color(style.fill, style.fill_opacity),
+        fill_options: FillOptions::default().with_fill_rule(style.fill_rule),
+        stroke: style.paint_color(style.stroke, style.stroke_opacity),
+        stroke_options: StrokeOptions::default()
+            .with_line_width(style.stroke_width * scale)
+            .with_line_cap(style.line_cap)
+            .with_line_join(style.line_join)
+            .with_miter_limit(style.miter_limit),
+    }
+}
+
+/// Builds the outline of a basic shape or path element. Returns `None` for
+/// unsupported elements and for shapes that must not be rendered.
+fn element_path(node: roxmltree::Node<'_, '_>) -> Result<Option<Path>, SvgError> {
+    let length = |name: &str| length_attribute(node, name);
+    let mut builder = Path::builder();
+
+    match node.tag_name().name() {
+        "path" => {
+            let mut builder = builder.with_svg();
+            parse_path_data(node.attribute("d").unwrap_or(""), &mut builder)?;
+            return Ok(Some(builder.build()));
+        }
+        "rect" => return rect_path(node),
+        "circle" => {
+            let r = length("r")?;
+            if r <= 0.0 {
+                return Ok(None);
+            }
+            builder.add_circle(point(length("cx")?, length("cy")?), r, Winding::Positive);
+        }
+        "ellipse" => {
+            let (rx, ry) = (length("rx")?, length("ry")?);
+            if rx <= 0.0 || ry <= 0.0 {
+                return Ok(None);
+            }
+            builder.add_ellipse(
+                point(length("cx")?, length("cy")?),
+                vector(rx, ry),
+                Angle::zero(),
+                Winding::Positive,
+            );
+        }
+        "line" => {
+            let from = point(length("x1")?, length("y1")?);
+            let to = point(length("x2")?, length("y2")?);
+            builder.add_polygon(Polygon {
+                points: &[from, to],
+                closed: false,
+            });
+        }
+        "polyline" => return polyline_path(node, false),
+        "polygon" => return polyline_path(node, true),
+        _ => return Ok(None),
+    }
+
+    Ok(Some(builder.build()))
+}
+
+/// Returns the value of a length attribute of `node`, or 0 if it is missing.
+fn length_attribute(node: roxmltree::Node<'_, '_>, name: &str) -> Result<f32, SvgError> {
+    node.attribute(name).map_or(Ok(0.0), |value| {
+        parse_length(value).ok_or_else(|| invalid_attribute(name, value))
+    })
+}
+
+/// Builds the outline of a `rect` element, with its rounded corners.
+fn rect_path(node: roxmltree::Node<'_, '_>) -> Result<Option<Path>, SvgError> {
+    let length = |name: &str| length_attribute(node, name);
+    let (x, y) = (length("x")?, length("y")?);
+    let (width, height) = (length("width")?, length("height")?);
+    if width <= 0.0 || height <= 0.0 {
+        return Ok(None);
+    }
+    // A missing corner radius defaults to the other one.
+    let (rx, ry) = match (node.attribute("rx"), node.attribute("ry")) {
+        (Some(_), None) => (length("rx")?, length("rx")?),
+        (None, Some(_)) => (length("ry")?, length("ry")?),
+        _ => (length("rx")?, length("ry")?),
+    };
+    let (rx, ry) = (rx.min(width / 2.0), ry.min(height / 2.0));
+    if rx <= 0.0 || ry <= 0.0 {
+        let mut builder = Path::builder();
+        builder.add_rectangle(
+            &Rect::new(point(x, y), Size::new(width, height)),
+            Winding::Positive,
+        );
+        return Ok(Some(builder.build()));
+    }
+
+    let mut builder = Path::builder().with_svg();
+    let radii = vector(rx, ry);
+    let arc = ArcFlags {
+        large_arc: false,
+        sweep: true,
+    };
+    builder.move_to(point(x + rx, y));
+    builder.horizontal_line_to(x + width - rx);
+    builder.arc_to(radii, Angle::zero(), arc, point(x + width, y + ry));
+    builder.vertical_line_to(y + height - ry);
+    builder.arc_to(radii, Angle::zero(), arc, point(x + width - rx, y + height));
+    builder.horizontal_line_to(x + rx);
+    builder.arc_to(radii, Angle::zero(), arc, point(x, y + height - ry));
+    builder.vertical_line_to(y + ry);
+    builder.arc_to(radii, Angle::zero(), arc, point(x + rx, y));
+    builder.close();
+
+    Ok(Some(builder.build()))
+}
+
+/// Builds the outline of a `polyline` or, if `closed`, `polygon` element.
+fn polyline_path(node: roxmltree::Node<'_, '_>, closed: bool) -> Result<Option<Path>, SvgError> {
+    let value = node.attribute("points").unwrap_or("");
+    let numbers = parse_number_list(value).map_err(|_| invalid_attribute("points", value))?;
+    // An odd number of coordinates is an error, but the points preceding it
+    // are still rendered.
+    let points = numbers
+        .chunks_exact(2)
+        .map(|xy| point(xy[0], xy[1]))
+        .collect::<Vec<Point>>();
+    if points.len() < 2 {
+        return Ok(None);
+    }
+
+    let mut builder = Path::builder();
+    builder.add_polygon(Polygon {
+        points: &points,
+        closed,
+    });
+
+    Ok(Some(builder.build()))
+}
+
+/// Returns the transform mapping the `viewBox` of the root element to its
+/// `width` and `height`.
+fn view_box_transform(root: roxmltree::Node<'_, '_>) -> Result<Transform2D, SvgError> {
+    let value = match root.attribute("viewBox") {
+        Some(value) => value,
+        None => return Ok(Transform2D::identity()),
+    };
+    let numbers = parse_number_list(value).map_err(|_| invalid_attribute("viewBox", value))?;
+    let (min_x, min_y, width, height) = match numbers[..] {
+        [min_x, min_y, width, height] if width > 0.0 && height > 0.0 => {
+            (min_x, min_y, width, height)
+        }
+        _ => return Err(invalid_attribute("viewBox", value)),
+    };
+    // Relative sizes like "100%" don't have a meaning here, so they are
+    // treated as if the size was not set.
+    let size = |name: &str, view_box_size: f32| {
+        root.attribute(name)
+            .and_then(parse_length)
+            .map_or(1.0, |size| size / view_box_size)
+    };
+
+    Ok(
+        Transform2D::translation(-min_x, -min_y).then(&Transform2D::scale(
+            size("width", width),
+            size("height", height),
+        )),
+    )
+}
+
+/// Parses the value of a `transform` attribute.
+#[allow(clippy::many_single_char_names)]
+fn parse_transform(value: &str) -> Result<Transform2D, SvgError> {
+    let invalid = || invalid_attribute("transform", value);
+    let mut transform = Transform2D::identity();
+    let mut rest = value.trim();
+
+    while !rest.is_empty() {
+        let open = rest.find('(').ok_or_else(invalid)?;
+        let close = rest.find(')').ok_or_else(invalid)?;
+        if close < open {
+            return Err(invalid());
+        }
+        let name = rest[..open].trim();
+        let arguments = parse_number_list(&rest[open + 1..close]).map_err(|_| invalid())?;
+
+        let function = match (name, &arguments[..]) {
+            ("matrix", &[a, b, c, d, e, f]) => Transform2D::new(a, b, c, d, e, f),
+            ("translate", &[x]) => Transform2D::translation(x, 0.0),
+            ("translate", &[x, y]) => Transform2D::translation(x, y),
+            ("scale", &[s]) => Transform2D::scale(s, s),
+            ("scale", &[x, y]) => Transform2D::scale(x, y),
+            ("rotate", &[angle]) => Transform2D::rotation(Angle::degrees(angle)),
+            ("rotate", &[angle, cx, cy]) => Transform2D::translation(-cx, -cy)
+                .then(&Transform2D::rotation(Angle::degrees(angle)))
+                .then(&Transform2D::translation(cx, cy)),
+            ("skewX", &[angle]) => {
+                Transform2D::new(1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0)
+            }
+            ("skewY", &[angle]) => {
+                Transform2D::new(1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0)
+            }
+            _ => return Err(invalid()),
+        };
+        // The rightmost function of the list is applied first.
+        transform = function.then(&transform);
+
+        rest = rest[close + 1..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+
+    Ok(transform)
+}
+
+fn parse_number(value: &str) -> Option<f32> {
+    value.trim().parse().ok()
+}
+
+/// Parses a number or a percentage, clamped between 0 and 1. Numbers are
+/// divided by `scale`.
+fn parse_fraction(value: &str, scale: f32) -> Option<f32> {
+    let value = value.trim();
+    let fraction = match value.strip_suffix('%') {
+        Some(percentage) => parse_number(percentage)? / 100.0,
+        None => parse_number(value)? / scale,
+    };
+
+    Some(fraction.max(0.0).min(1.0))
+}
+
+fn parse_opacity(value: &str) -> Option<f32> {
+    parse_fraction(value, 1.0)
+}
+
+/// Parses a length, converting absolute units to pixels.
+///
+/// Font-relative units use the default font size of 16 pixels, since text is
+/// not supported. Percentages are not supported.
+fn parse_length(value: &str) -> Option<f32> {
+    const UNITS: [(&str, f32); 8] = [
+        ("px", 1.0),
+        ("in", 96.0),
+        ("cm", 96.0 / 2.54),
+        ("mm", 96.0 / 25.4),
+        ("pt", 96.0 / 72.0),
+        ("pc", 16.0),
+        ("em", 16.0),
+        ("ex", 8.0),
+    ];
+
+    let value = value.trim();
+    let (number, factor) = UNITS
+        .iter()
+        .find(|(unit, _)| value.ends_with(unit))
+        .map_or((value, 1.0), |&(unit, factor)| {
+            (&value[..value.len() - unit.len()], factor)
+        });
+
+    parse_number(number).map(|number| number * factor)
+}
+
+/// Parses a fill or stroke paint. Returns `None` if the paint is not
+/// supported.
+///
+/// The paint servers are not supported: they are replaced by their fallback
+/// color, or by `none` if they have none.
+fn parse_paint(name: &str, value: &str) -> Option<Paint> {
+    if value == "none" {
+        return Some(Paint::None);
+    }
+    if value.eq_ignore_ascii_case("currentColor") {
+        return Some(Paint::CurrentColor);
+    }
+    if let Some(reference) = value.strip_prefix("url(") {
+        let fallback = reference
+            .find(')')
+            .map_or("", |close| reference[close + 1..].trim());
+        if fallback.is_empty() {
+            warn!("unsupported SVG paint {:?}, the {} is ignored", value, name);
+            return Some(Paint::None);
+        }
+        return parse_paint(name, fallback);
+    }
+
+    parse_color(value).map(Paint::Color)
+}
+
+/// Parses a CSS color: in hexadecimal notation, with the `rgb()`, `rgba()`,
+/// `hsl()` and `hsla()` functions, or as a keyword.
+fn parse_color(value: &str) -> Option<[f32; 4]> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        return parse_hex_color(hex);
+    }
+    if let Some(open) = value.find('(') {
+        let arguments = value[open + 1..].strip_suffix(')')?;
+        return parse_color_function(value[..open].trim(), arguments);
+    }
+    if value == "transparent" {
+        return Some([0.0; 4]);
+    }
+
+    NAMED_COLORS
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, rgb)| {
+            [
+                f32::from(rgb[0]) / 255.0,
+                f32::from(rgb[1]) / 255.0,
+                f32::from(rgb[2]) / 255.0,
+                1.0,
+            ]
+        })
+}
+
+fn parse_hex_color(hex: &str) -> Option<[f32; 4]> {
+    let digits = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as f32))
+        .collect::<Option<Vec<f32>>>()?;
+    let byte = |high: f32, low: f32| high.mul_add(16.0, low) / 255.0;
+
+    match digits[..] {
+        [r, g, b] => Some([r / 15.0, g / 15.0, b / 15.0, 1.0]),
+        [r, g, b, a] => Some([r / 15.0, g / 15.0, b / 15.0, a / 15.0]),
+        [r1, r2, g1, g2, b1, b2] => Some([byte(r1, r2), byte(g1, g2), byte(b1, b2), 1.0]),
+        [r1, r2, g1, g2, b1, b2, a1, a2] => {
+            Some([byte(r1, r2), byte(g1, g2), byte(b1, b2), byte(a1, a2)])
+        }
+        _ => None,
+    }
+}
+
+/// Parses the arguments of a color function. They are separated by commas, or
+/// by whitespace with a `/` before the alpha.
+fn parse_color_function(function: &str, arguments: &str) -> Option<[f32; 4]> {
+    let arguments = arguments
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|argument| !argument.is_empty())
+        .collect::<Vec<_>>();
+    let alpha = match arguments[..] {
+        [_, _, _] => 1.0,
+        [_, _, _, alpha] => parse_fraction(alpha, 1.0)?,
+        _ => return None,
+    };
+
+    let [r, g, b] = match function {
+        "rgb" | "rgba" => [
+            parse_fraction(arguments[0], 255.0)?,
+            parse_fraction(arguments[1], 255.0)?,
+            parse_fraction(arguments[2], 255.0)?,
+        ],
+        "hsl" | "hsla" => {
+            let hue = arguments[0];
+            let hue = parse_number(hue.strip_suffix("deg").unwrap_or(hue))?;
+            hsl_to_rgb(
+                hue,
+                parse_fraction(arguments[1], 100.0)?,
+                parse_fraction(arguments[2], 100.0)?,
+            )
+        }
+        _ => return None,
+    };
+
+    Some([r, g, b, alpha])
+}
+
+/// Converts a color from HSL, with the hue in degrees, to RGB.
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let hue = hue.rem_euclid(360.0) / 30.0;
+    let a = saturation * lightness.min(1.0 - lightness);
+    let channel = |n: f32| {
+        let k = (n + hue) % 12.0;
+        lightness - a * (k - 3.0).min(9.0 - k).min(1.0).max(-1.0)
+    };
+
+    [channel(0.0), channel(8.0), channel(4.0)]
+}
+
+/// The CSS color keywords, with their sRGB components.
+const NAMED_COLORS: [(&str, [u8; 3]); 148] = [
+    ("aliceblue", [240, 248, 255]),
+    ("antiquewhite", [250, 235, 215]),
+    ("aqua", [0, 255, 255]),
+    ("aquamarine", [127, 255, 212]),
+    ("azure", [240, 255, 255]),
+    ("beige", [245, 245, 220]),
+    ("bisque", [255, 228, 196]),
+    ("black", [0, 0, 0]),
+    ("blanchedalmond", [255, 235, 205]),
+    ("blue", [0, 0, 255]),
+    ("blueviolet", [138, 43, 226]),
+    ("brown", [165, 42, 42]),
+    ("burlywood", [222, 184, 135]),
+    ("cadetblue", [95, 158, 160]),
+    ("chartreuse", [127, 255, 0]),
+    ("chocolate", [210, 105, 30]),
+    ("coral", [255, 127, 80]),
+    ("cornflowerblue", [100, 149, 237]),
+    ("cornsilk", [255, 248, 220]),
+    ("crimson", [220, 20, 60]),
+    ("cyan", [0, 255, 255]),
+    ("darkblue", [0, 0, 139]),
+    ("darkcyan", [0, 139, 139]),
+    ("darkgoldenrod", [184, 134, 11]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgreen", [0, 100, 0]),
+    ("darkgrey", [169, 169, 169]),
+    ("darkkhaki", [189, 183, 107]),
+    ("darkmagenta", [139, 0, 139]),
+    ("darkolivegreen", [85, 107, 47]),
+    ("darkorange", [255, 140, 0]),
+    ("darkorchid", [153, 50, 204]),
+    ("darkred", [139, 0, 0]),
+    ("darksalmon", [233, 150, 122]),
+    ("darkseagreen", [143, 188, 143]),
+    ("darkslateblue", [72, 61, 139]),
+    ("darkslategray", [47, 79, 79]),
+    ("darkslategrey", [47, 79, 79]),
+    ("darkturquoise", [0, 206, 209]),
+    ("darkviolet", [148, 0, 211]),
+    ("deeppink", [255, 20, 147]),
+    ("deepskyblue", [0, 191, 255]),
+    ("dimgray", [105, 105, 105]),
+    ("dimgrey", [105, 105, 105]),
+    ("dodgerblue", [30, 144, 255]),
+    ("firebrick", [178, 34, 34]),
+    ("floralwhite", [255, 250, 240]),
+    ("forestgreen", [34, 139, 34]),
+    ("fuchsia", [255, 0, 255]),
+    ("gainsboro", [220, 220, 220]),
+    ("ghostwhite", [248, 248, 255]),
+    ("gold", [255, 215, 0]),
+    ("goldenrod", [218, 165, 32]),
+    ("gray", [128, 128, 128]),
+    ("green", [0, 128, 0]),
+    ("greenyellow", [173, 255, 47]),
+    ("grey", [128, 128, 128]),
+    ("honeydew", [240, 255, 240]),
+    ("hotpink", [255, 105, 180]),
+    ("indianred", [205, 92, 92]),
+    ("indigo", [75, 0, 130]),
+    ("ivory", [255, 255, 240]),
+    ("khaki", [240, 230, 140]),
+    ("lavender", [230, 230, 250]),
+    ("lavenderblush", [255, 240, 245]),
+    ("lawngreen", [124, 252, 0]),
+    ("lemonchiffon", [255, 250, 205]),
+    ("lightblue", [173, 216, 230]),
+    ("lightcoral", [240, 128, 128]),
+    ("lightcyan", [224, 255, 255]),
+    ("lightgoldenrodyellow", [250, 250, 210]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgreen", [144, 238, 144]),
+    ("lightgrey", [211, 211, 211]),
+    ("lightpink", [255, 182, 193]),
+    ("lightsalmon", [255, 160, 122]),
+    ("lightseagreen", [32, 178, 170]),
+    ("lightskyblue", [135, 206, 250]),
+    ("lightslategray", [119, 136, 153]),
+    ("lightslategrey", [119, 136, 153]),
+    ("lightsteelblue", [176, 196, 222]),
+    ("lightyellow", [255, 255, 224]),
+    ("lime", [0, 255, 0]),
+    ("limegreen", [50, 205, 50]),
+    ("linen", [250, 240, 230]),
+    ("magenta", [255, 0, 255]),
+    ("maroon", [128, 0, 0]),
+    ("mediumaquamarine", [102, 205, 170]),
+    ("mediumblue", [0, 0, 205]),
+    ("mediumorchid", [186, 85, 211]),
+    ("mediumpurple", [147, 112, 219]),
+    ("mediumseagreen", [60, 179, 113]),
+    ("mediumslateblue", [123, 104, 238]),
+    ("mediumspringgreen", [0, 250, 154]),
+    ("mediumturquoise", [72, 209, 204]),
+    ("mediumvioletred", [199, 21, 133]),
+    ("midnightblue", [25, 25, 112]),
+    ("mintcream", [245, 255, 250]),
+    ("mistyrose", [255, 228, 225]),
+    ("moccasin", [255, 228, 181]),
+    ("navajowhite", [255, 222, 173]),
+    ("navy", [0, 0, 128]),
+    ("oldlace", [253, 245, 230]),
+    ("olive", [128, 128, 0]),
+    ("olivedrab", [107, 142, 35]),
+    ("orange", [255, 165, 0]),
+    ("orangered", [255, 69, 0]),
+    ("orchid", [218, 112, 214]),
+    ("palegoldenrod", [238, 232, 170]),
+    ("palegreen", [152, 251, 152]),
+    ("paleturquoise", [175, 238, 238]),
+    ("palevioletred", [219, 112, 147]),
+    ("papayawhip", [255, 239, 213]),
+    ("peachpuff", [255, 218, 185]),
+    ("peru", [205, 133, 63]),
+    ("pink", [255, 192, 203]),
+    ("plum", [221, 160, 221]),
+    ("powderblue", [176, 224, 230]),
+    ("purple", [128, 0, 128]),
+    ("rebeccapurple", [102, 51, 153]),
+    ("red", [255, 0, 0]),
+    ("rosybrown", [188, 143, 143]),
+    ("royalblue", [65, 105, 225]),
+    ("saddlebrown", [139, 69, 19]),
+    ("salmon", [250, 128, 114]),
+    ("sandybrown", [244, 164, 96]),
+    ("seagreen", [46, 139, 87]),
+    ("seashell", [255, 245, 238]),
+    ("sienna", [160, 82, 45]),
+    ("silver", [192, 192, 192]),
+    ("skyblue", [135, 206, 235]),
+    ("slateblue", [106, 90, 205]),
+    ("slategray", [112, 128, 144]),
+    ("slategrey", [112, 128, 144]),
+    ("snow", [255, 250, 250]),
+    ("springgreen", [0, 255, 127]),
+    ("steelblue", [70, 130, 180]),
+    ("tan", [210, 180, 140]),
+    ("teal", [0, 128, 128]),
+    ("thistle", [216, 191, 216]),
+    ("tomato", [255, 99, 71]),
+    ("turquoise", [64, 224, 208]),
+    ("violet", [238, 130, 238]),
+    ("wheat", [245, 222, 179]),
+    ("white", [255, 255, 255]),
+    ("whitesmoke", [245, 245, 245]),
+    ("yellow", [255, 255, 0]),
+    ("yellowgreen", [154, 205, 50]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(body: &str) -> Vec<SvgShape> {
+        let text = format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#, body);
+        Svg::parse(&text)
+            .expect("the document should be valid")
+            .shapes
+    }
+
+    fn rgba(color: Option<Color>) -> [f32; 4] {
+        let color = color.expect("the paint should be a color");
+
+        [color.r(), color.g(), color.b(), color.a()]
+    }
+
+    fn assert_color(actual: Option<Color>, expected: [f32; 4]) {
+        let actual = rgba(actual);
+        assert!(
+            actual
+                .iter()
+                .zip(&expected)
+                .all(|(a, e)| (a - e).abs() < 1e-3),
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn fill(paint: &str) -> Option<Color> {
+        parse(&format!(r#"<rect width="1" height="1" fill="{}"/>"#, paint))[0].fill
+    }
+
+    #[test]
+    fn color_notations() {
+        assert_color(fill("#f00"), [1.0, 0.0, 0.0, 1.0]);
+        assert_color(fill("#00ff0080"), [0.0, 1.0, 0.0, 128.0 / 255.0]);
+        assert_color(fill("rgb(255, 0, 0)"), [1.0, 0.0, 0.0, 1.0]);
+        assert_color(fill("rgb(100%, 50%, 0%)"), [1.0, 0.5, 0.0, 1.0]);
+        assert_color(fill("rgba(0, 0, 255, 0.5)"), [0.0, 0.0, 1.0, 0.5]);
+        assert_color(fill("rgb(0 0 255 / 25%)"), [0.0, 0.0, 1.0, 0.25]);
+        assert_color(fill("hsl(120, 100%, 50%)"), [0.0, 1.0, 0.0, 1.0]);
+        assert_color(fill("hsla(240deg 100% 25% / 0.5)"), [0.0, 0.0, 0.5, 0.5]);
+        assert_color(fill("transparent"), [0.0; 4]);
+        assert_color(fill("RebeccaPurple"), [0.4, 0.2, 0.6, 1.0]);
+        assert_color(
+            fill("lightgoldenrodyellow"),
+            [250.0 / 255.0, 250.0 / 255.0, 210.0 / 255.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn current_color() {
+        let shapes = parse(
+            r##"<g color="#00f"><rect width="1" height="1" fill="currentColor"/></g>
+            <rect width="1" height="1" fill="currentcolor" color="red"/>
+            <g style="color: lime"><rect width="1" height="1" stroke="currentColor"/></g>"##,
+        );
+        assert_color(shapes[0].fill, [0.0, 0.0, 1.0, 1.0]);
+        assert_color(shapes[1].fill, [1.0, 0.0, 0.0, 1.0]);
+        assert_color(shapes[2].stroke, [0.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn paint_servers() {
+        assert!(fill("url(#gradient)").is_none());
+        assert_color(fill("url(#gradient) red"), [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn unsupported_values_are_ignored() {
+        let shapes = parse(
+            r#"<g fill="red" stroke-width="3">
+                <rect width="1" height="1" fill="color(display-p3 1 0 0)" stroke="blue"
+                    stroke-width="50%" stroke-linecap="diagonal" fill-rule="sometimes"/>
+            </g>"#,
+        );
+        assert_color(shapes[0].fill, [1.0, 0.0, 0.0, 1.0]);
+        assert!((shapes[0].stroke_options.line_width - 3.0).abs() < 1e-6);
+        assert_eq!(shapes[0].stroke_options.start_cap, LineCap::Butt);
+        assert_eq!(shapes[0].fill_options.fill_rule, FillRule::NonZero);
+    }
+
+    #[test]
+    fn lengths() {
+        let shapes = parse(
+            r#"<rect width="1" height="1" stroke="red" stroke-width="1em"/>
+            <rect width="1" height="1" stroke="red" stroke-width="0.5in"/>"#,
+        );
+        assert!((shapes[0].stroke_options.line_width - 16.0).abs() < 1e-6);
+        assert!((shapes[1].stroke_options.line_width - 48.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn elements_with_unsupported_geometry_are_skipped() {
+        let shapes = parse(
+            r#"<rect width="50%" height="10" fill="red"/>
+            <circle r="5" fill="blue"/>"#,
+        );
+        assert_eq!(shapes.len(), 1);
+        assert_color(shapes[0].fill, [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn opacity() {
+        let shapes = parse(
+            r#"<rect width="1" height="1" fill="red" opacity="0.5" style="opacity: 0.5"/>
+            <rect width="1" height="1" fill="red" opacity="0.2" style="opacity: 50%"/>
+            <g opacity="0.5"><rect width="1" height="1" fill="red" opacity="0.5"/></g>
+            <g opacity="0.5"><rect width="1" height="1" fill="red" fill-opacity="0.5"/></g>"#,
+        );
+        assert_color(shapes[0].fill, [1.0, 0.0, 0.0, 0.5]);
+        assert_color(shapes[1].fill, [1.0, 0.0, 0.0, 0.5]);
+        assert_color(shapes[2].fill, [1.0, 0.0, 0.0, 0.25]);
+        assert_color(shapes[3].fill, [1.0, 0.0, 0.0, 0.25]);
+    }
+
+    #[test]
+    fn malformed_path_data_is_an_error() {
+        let text = r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M 0 0 L 1"/></svg>"#;
+        assert!(matches!(Svg::parse(text), Err(SvgError::PathData(_))));
+    }
+}
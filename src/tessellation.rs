@@ -0,0 +1,393 @@
+//! Conversion of the geometry of a shape into mesh data.
+
+use crate::{
+    geometry::path_bounds,
+    paint::{FillUvMapping, StrokeUvMapping, UvMapping},
+    path::WIDTH_ATTRIBUTE,
+    render::ATTRIBUTE_COLOR,
+    stroke::{stroked_path_with_distances, StrokeDash, StrokeTrim},
+    utils::{Convert, TessellationMode},
+};
+use bevy::{
+    math::{Vec2, Vec3},
+    render::{
+        mesh::{Indices, Mesh, VertexAttributeValues},
+        pipeline::PrimitiveTopology,
+    },
+    transform::components::GlobalTransform,
+};
+use lyon_tessellation::{
+    self as tess,
+    math::Point,
+    path::{AttributeStore, Path},
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, Side,
+    StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor, TessellationError,
+};
+
+/// The index type of a Bevy [`Mesh`](bevy::render::mesh::Mesh).
+type IndexType = u32;
+/// Lyon's [`VertexBuffers`] generic data type defined for [`Vertex`].
+pub(crate) type VertexBuffers = tess::VertexBuffers<Vertex, IndexType>;
+
+/// A vertex with all the necessary attributes to be inserted into a Bevy
+/// [`Mesh`](bevy::render::mesh::Mesh).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+impl Vertex {
+    fn new(position: Point, uv: [f32; 2], attributes: &[f32]) -> Self {
+        Self {
+            position: [position.x, position.y, 0.0],
+            normal: [0.0, 0.0, 1.0],
+            uv,
+            color: vertex_color(attributes),
+        }
+    }
+}
+
+/// Returns the vertex color stored in the first four attributes of a path, or
+/// white if the path has no color attributes.
+fn vertex_color(attributes: &[f32]) -> [f32; 4] {
+    match *attributes {
+        [r, g, b, a, ..] => [r, g, b, a],
+        _ => [1.0; 4],
+    }
+}
+
+/// The components of a shape that determine its tessellated geometry.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ShapeGeometry<'a> {
+    pub(crate) path: &'a Path,
+    pub(crate) mode: &'a TessellationMode,
+    pub(crate) uv_mapping: &'a UvMapping,
+    pub(crate) dash: &'a StrokeDash,
+    pub(crate) trim: &'a StrokeTrim,
+}
+
+/// The tessellated geometry of a shape.
+pub(crate) struct Tessellation {
+    pub(crate) buffers: VertexBuffers,
+    /// The geometry of the outline child entity, if the shape has one.
+    pub(crate) outline_buffers: Option<VertexBuffers>,
+}
+
+/// Builds the vertices of the fill geometry.
+///
+/// The texture coordinates are an affine function of the position:
+/// `uv = position * uv_scale + uv_offset`.
+struct FillConstructor {
+    uv_scale: Vec2,
+    uv_offset: Vec2,
+}
+
+impl FillVertexConstructor<Vertex> for FillConstructor {
+    fn new_vertex(&mut self, mut vertex: FillVertex) -> Vertex {
+        let position = vertex.position();
+        let point: Vec2 = position.convert();
+        let uv = point * self.uv_scale + self.uv_offset;
+        Vertex::new(position, [uv.x, uv.y], vertex.interpolated_attributes())
+    }
+}
+
+/// Builds the vertices of the stroke geometry.
+///
+/// The `u` coordinate is the distance along the path multiplied by `u_scale`.
+/// The vertices are moved away from the path according to the stroke width
+/// stored in the path attributes, if any.
+///
+/// The attributes after the `num_attributes` of the shape path hold the
+/// distance to the start of the dash or trimmed part of a stroked path.
+struct StrokeConstructor {
+    u_scale: f32,
+    num_attributes: usize,
+}
+
+impl StrokeVertexConstructor<Vertex> for StrokeConstructor {
+    fn new_vertex(&mut self, mut vertex: StrokeVertex) -> Vertex {
+        let (position_on_path, normal) = (vertex.position_on_path(), vertex.normal());
+        let mut position = vertex.position();
+        let advancement = vertex.advancement();
+        let v = match vertex.side() {
+            Side::Left => 0.0,
+            Side::Right => 1.0,
+        };
+
+        let attributes = vertex.interpolated_attributes();
+        let (attributes, piece_start) =
+            attributes.split_at(self.num_attributes.min(attributes.len()));
+        let u = (advancement + piece_start.first().copied().unwrap_or(0.0)) * self.u_scale;
+        if let Some(&width) = attributes.get(WIDTH_ATTRIBUTE) {
+            if width >= 0.0 {
+                position = position_on_path + normal * (width / 2.0);
+            }
+        }
+        Vertex::new(position, [u, v], attributes)
+    }
+}
+
+/// Tessellates the geometry of a shape, producing the buffers of its mesh and,
+/// for [`TessellationMode::FillAndStroke`], of its outline.
+pub(crate) fn tessellate(
+    fill_tess: &mut FillTessellator,
+    stroke_tess: &mut StrokeTessellator,
+    geometry: &ShapeGeometry<'_>,
+) -> Result<Tessellation, TessellationError> {
+    let mut buffers = VertexBuffers::new();
+    let mut outline_buffers = None;
+
+    match *geometry.mode {
+        TessellationMode::Fill(options) => {
+            fill(fill_tess, geometry, options, &mut buffers)?;
+        }
+        TessellationMode::Stroke(ref options) => {
+            stroke(stroke_tess, geometry, options, &mut buffers)?;
+        }
+        TessellationMode::FillAndStroke(fill_options, ref stroke_options) => {
+            let mut stroke_buffers = VertexBuffers::new();
+            fill(fill_tess, geometry, fill_options, &mut buffers)?;
+            stroke(stroke_tess, geometry, stroke_options, &mut stroke_buffers)?;
+            outline_buffers = Some(stroke_buffers);
+        }
+    }
+
+    Ok(Tessellation {
+        buffers,
+        outline_buffers,
+    })
+}
+
+fn fill(
+    fill_tess: &mut FillTessellator,
+    geometry: &ShapeGeometry<'_>,
+    options: FillOptions,
+    buffers: &mut VertexBuffers,
+) -> Result<(), TessellationError> {
+    let (uv_scale, uv_offset) = fill_uv_transform(geometry.path, geometry.uv_mapping.fill);
+    let constructor = FillConstructor {
+        uv_scale,
+        uv_offset,
+    };
+
+    fill_tess
+        .tessellate_path(
+            geometry.path,
+            &options,
+            &mut BuffersBuilder::new(buffers, constructor),
+        )
+        .map(|_| ())
+}
+
+fn stroke(
+    stroke_tess: &mut StrokeTessellator,
+    geometry: &ShapeGeometry<'_>,
+    options: &StrokeOptions,
+    buffers: &mut VertexBuffers,
+) -> Result<(), TessellationError> {
+    let constructor = StrokeConstructor {
+        u_scale: match geometry.uv_mapping.stroke {
+            StrokeUvMapping::Stretch => 1.0,
+            StrokeUvMapping::Tile(length) => inverse(length),
+        },
+        num_attributes: geometry.path.num_attributes(),
+    };
+
+    let stroked;
+    let (path, stretch_length) = if geometry.dash.is_solid() && geometry.trim.is_full() {
+        (geometry.path, None)
+    } else {
+        stroked = stroked_path_with_distances(
+            geometry.path,
+            geometry.dash,
+            *geometry.trim,
+            options.tolerance,
+        );
+        (&stroked.0, Some(stroked.1))
+    };
+
+    let first_vertex = buffers.vertices.len();
+    stroke_tess.tessellate_path(
+        path,
+        options,
+        &mut BuffersBuilder::new(buffers, constructor),
+    )?;
+
+    // The length of the path is only known once it has been walked, or
+    // measured to be dashed or trimmed, in which case the texture is stretched
+    // over the whole path rather than over its visible pieces.
+    if geometry.uv_mapping.stroke == StrokeUvMapping::Stretch {
+        let vertices = &mut buffers.vertices[first_vertex..];
+        let length = stretch_length
+            .unwrap_or_else(|| vertices.iter().fold(0.0_f32, |max, v| max.max(v.uv[0])));
+        let scale = inverse(length);
+        for vertex in vertices {
+            vertex.uv[0] *= scale;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the scale and offset mapping the positions of the fill geometry to
+/// its texture coordinates.
+fn fill_uv_transform(path: &Path, mapping: FillUvMapping) -> (Vec2, Vec2) {
+    if let FillUvMapping::Tile(size) = mapping {
+        return (Vec2::new(inverse(size.x), -inverse(size.y)), Vec2::zero());
+    }
+
+    let bounds = match path_bounds(path) {
+        Some(bounds) => bounds,
+        None => return (Vec2::zero(), Vec2::zero()),
+    };
+    let size = bounds.size();
+    let size = match mapping {
+        FillUvMapping::PreserveAspect => Vec2::splat(size.x.max(size.y)),
+        _ => size,
+    };
+    // The center of the bounding box is mapped to (0.5, 0.5), and `v` grows
+    // downwards.
+    let center = bounds.center();
+    let scale = Vec2::new(inverse(size.x), -inverse(size.y));
+
+    (scale, Vec2::splat(0.5) - center * scale)
+}
+
+/// Returns `1 / x`, or 0 if `x` is not strictly positive.
+fn inverse(x: f32) -> f32 {
+    if x > 0.0 {
+        1.0 / x
+    } else {
+        0.0
+    }
+}
+
+pub(crate) fn build_mesh(buffers: &VertexBuffers) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_indices(Some(Indices::U32(buffers.indices.clone())));
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        buffers
+            .vertices
+            .iter()
+            .map(|v| v.position)
+            .collect::<Vec<[f32; 3]>>(),
+    );
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        buffers
+            .vertices
+            .iter()
+            .map(|v| v.normal)
+            .collect::<Vec<[f32; 3]>>(),
+    );
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        buffers
+            .vertices
+            .iter()
+            .map(|v| v.uv)
+            .collect::<Vec<[f32; 2]>>(),
+    );
+    mesh.set_attribute(
+        ATTRIBUTE_COLOR,
+        buffers
+            .vertices
+            .iter()
+            .map(|v| v.color)
+            .collect::<Vec<[f32; 4]>>(),
+    );
+
+    mesh
+}
+
+/// Appends the vertices and triangles of a mesh built by [`build_mesh`] to
+/// `buffers`, moving them by `transform`.
+///
+/// The buffers are left unchanged if the mesh doesn't have the attributes and
+/// indices of a shape mesh.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn append_mesh(buffers: &mut VertexBuffers, mesh: &Mesh, transform: &GlobalTransform) {
+    let attributes = (
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+        mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+        mesh.attribute(Mesh::ATTRIBUTE_UV_0),
+        mesh.attribute(ATTRIBUTE_COLOR),
+    );
+    let (positions, normals, uvs, colors) = match attributes {
+        (
+            Some(VertexAttributeValues::Float3(positions)),
+            Some(VertexAttributeValues::Float3(normals)),
+            Some(VertexAttributeValues::Float2(uvs)),
+            Some(VertexAttributeValues::Float4(colors)),
+        ) => (positions, normals, uvs, colors),
+        _ => return,
+    };
+
+    let offset = buffers.vertices.len() as IndexType;
+    match mesh.indices() {
+        Some(Indices::U32(indices)) => buffers
+            .indices
+            .extend(indices.iter().map(|index| index + offset)),
+        Some(Indices::U16(indices)) => buffers
+            .indices
+            .extend(indices.iter().map(|index| IndexType::from(*index) + offset)),
+        None => return,
+    }
+    let matrix = transform.compute_matrix();
+    buffers
+        .vertices
+        .extend(positions.iter().zip(normals).zip(uvs).zip(colors).map(
+            |(((position, normal), uv), color)| Vertex {
+                position: matrix.transform_point3((*position).into()).into(),
+                normal: (transform.rotation * Vec3::from(*normal)).into(),
+                uv: *uv,
+                color: *color,
+            },
+        ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PathBuilder;
+
+    #[test]
+    fn endpoints_without_width_use_the_line_width() {
+        let mut builder = PathBuilder::new();
+        let start = builder.move_to(Vec2::new(0.0, 0.0));
+        builder.set_width(start, 8.0);
+        builder.line_to(Vec2::new(10.0, 0.0));
+        builder.move_to(Vec2::new(0.0, 20.0));
+        builder.line_to(Vec2::new(10.0, 20.0));
+        let path = builder.build();
+
+        let mode = TessellationMode::Stroke(StrokeOptions::default().with_line_width(2.0));
+        let geometry = ShapeGeometry {
+            path: &path,
+            mode: &mode,
+            uv_mapping: &UvMapping::default(),
+            dash: &StrokeDash::default(),
+            trim: &StrokeTrim::default(),
+        };
+        let tessellation = tessellate(
+            &mut FillTessellator::new(),
+            &mut StrokeTessellator::new(),
+            &geometry,
+        )
+        .unwrap();
+
+        // The first sub-path is 8 wide, the second one is as wide as the line.
+        let vertices = &tessellation.buffers.vertices;
+        assert!(!vertices.is_empty());
+        for vertex in vertices {
+            let y = vertex.position[1];
+            let half_width = if y < 10.0 { y.abs() } else { (y - 20.0).abs() };
+            let expected = if y < 10.0 { 4.0 } else { 1.0 };
+            assert!((half_width - expected).abs() < 1e-4, "{:?}", vertex);
+        }
+    }
+}
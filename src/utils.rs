@@ -13,6 +13,12 @@ pub enum TessellationMode {
     Fill(FillOptions),
     /// The shape will be filled with the provided [`StrokeOptions`].
     Stroke(StrokeOptions),
+    /// The shape will be filled with the provided [`FillOptions`] and outlined
+    /// with the provided [`StrokeOptions`].
+    ///
+    /// The outline is rendered above the fill, using the material stored in
+    /// the [`OutlineMaterial`](crate::entity::OutlineMaterial) component.
+    FillAndStroke(FillOptions, StrokeOptions),
 }
 
 /// A locally defined [`std::convert::Into`] surrogate to overcome orphan rules.